@@ -0,0 +1,57 @@
+use super::*;
+
+pub const VOLUMES: [f64; 11] = [0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Bus {
+    Music,
+    Sfx,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Mixer {
+    pub master: f64,
+    pub music: f64,
+    pub sfx: f64,
+    pub music_enabled: bool,
+    pub mute_unfocused: bool,
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            music: 1.0,
+            sfx: 1.0,
+            music_enabled: true,
+            mute_unfocused: true,
+        }
+    }
+}
+
+impl Mixer {
+    pub fn volume(&self, bus: Bus, focused: bool) -> f64 {
+        if self.mute_unfocused && !focused {
+            return 0.0;
+        }
+        self.master
+            * match bus {
+                Bus::Music if self.music_enabled => self.music,
+                Bus::Music => 0.0,
+                Bus::Sfx => self.sfx,
+            }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn window_focused() -> bool {
+    web_sys::window()
+        .and_then(|window| window.document())
+        .map_or(true, |document| document.has_focus().unwrap_or(true))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn window_focused() -> bool {
+    true
+}
@@ -1,10 +1,65 @@
 use super::*;
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    Follow,
+    Rotating,
+    Overview,
+    Cinematic,
+}
+
+impl CameraMode {
+    pub const ALL: [Self; 4] = [
+        Self::Follow,
+        Self::Rotating,
+        Self::Overview,
+        Self::Cinematic,
+    ];
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Follow => "FOLLOW",
+            Self::Rotating => "ROTATING",
+            Self::Overview => "OVERVIEW",
+            Self::Cinematic => "CINEMATIC",
+        }
+    }
+}
+
+const TRAUMA_DECAY: f32 = 1.5;
+const MAX_SHAKE_OFFSET: f32 = 0.05;
+const MAX_SHAKE_ROTATION: f32 = 0.1;
+const SHAKE_FREQUENCY: f32 = 15.0;
+
+fn noise(seed: f32, t: f32) -> f32 {
+    ((t + seed).sin() + (t * 2.3 + seed * 3.1).sin() * 0.5 + (t * 4.7 + seed * 5.3).sin() * 0.25)
+        / 1.75
+}
+
+pub const DEFAULT_STIFFNESS: f32 = 6.0;
+
+fn spring(offset: f32, velocity: f32, stiffness: f32, delta_time: f32) -> (f32, f32) {
+    let exp = (-stiffness * delta_time).exp();
+    let temp = (velocity + stiffness * offset) * delta_time;
+    ((offset + temp) * exp, (velocity - stiffness * temp) * exp)
+}
+
+fn normalize_angle(angle: f32) -> f32 {
+    (angle + f32::PI).rem_euclid(2.0 * f32::PI) - f32::PI
+}
+
 pub struct Camera {
     pub center: Vec2<f32>,
     pub target_position: Vec2<f32>,
     pub fov: f32,
     pub target_fov: f32,
+    pub rotation: f32,
+    pub target_rotation: f32,
+    pub stiffness: f32,
+    velocity: Vec2<f32>,
+    fov_velocity: f32,
+    rotation_velocity: f32,
+    trauma: f32,
+    shake_time: f32,
 }
 
 impl Camera {
@@ -14,15 +69,81 @@ impl Camera {
             fov,
             target_fov: fov,
             target_position: vec2(0.0, 0.0),
+            rotation: 0.0,
+            target_rotation: 0.0,
+            stiffness: DEFAULT_STIFFNESS,
+            velocity: vec2(0.0, 0.0),
+            fov_velocity: 0.0,
+            rotation_velocity: 0.0,
+            trauma: 0.0,
+            shake_time: 0.0,
         }
     }
+    pub fn fit(
+        min: Vec2<f32>,
+        max: Vec2<f32>,
+        viewport: &Viewport,
+        framebuffer_size: Vec2<f32>,
+    ) -> Self {
+        let size = max - min;
+        let scale = (size.x / viewport.size.x).max(size.y / viewport.size.y);
+        let mut camera = Self::new(framebuffer_size.y * scale);
+        camera.center = (min + max) / 2.0
+            - (viewport.pos + viewport.size / 2.0 - framebuffer_size / 2.0) * scale;
+        camera.target_position = camera.center;
+        camera
+    }
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
     pub fn update(&mut self, delta_time: f32) {
-        let delta_time = delta_time * 5.0;
-        self.fov += (self.target_fov - self.fov) * delta_time.min(1.0);
-        self.center += (self.target_position - self.center) * delta_time.min(1.0);
+        self.trauma = (self.trauma - TRAUMA_DECAY * delta_time).max(0.0);
+        self.shake_time += delta_time * SHAKE_FREQUENCY;
+        let stiffness = self.stiffness;
+        let (x, vx) = spring(
+            self.center.x - self.target_position.x,
+            self.velocity.x,
+            stiffness,
+            delta_time,
+        );
+        let (y, vy) = spring(
+            self.center.y - self.target_position.y,
+            self.velocity.y,
+            stiffness,
+            delta_time,
+        );
+        self.center = self.target_position + vec2(x, y);
+        self.velocity = vec2(vx, vy);
+        let (fov, fov_velocity) = spring(
+            self.fov - self.target_fov,
+            self.fov_velocity,
+            stiffness,
+            delta_time,
+        );
+        self.fov = self.target_fov + fov;
+        self.fov_velocity = fov_velocity;
+        let offset = normalize_angle(self.rotation - self.target_rotation);
+        let (rotation, rotation_velocity) =
+            spring(offset, self.rotation_velocity, stiffness, delta_time);
+        self.rotation += rotation - offset;
+        self.rotation_velocity = rotation_velocity;
+    }
+    pub fn snap(&mut self) {
+        self.center = self.target_position;
+        self.fov = self.target_fov;
+        self.rotation = self.target_rotation;
+        self.velocity = vec2(0.0, 0.0);
+        self.fov_velocity = 0.0;
+        self.rotation_velocity = 0.0;
     }
     fn view_matrix(&self) -> Mat4<f32> {
-        Mat4::scale_uniform(1.0 / self.fov) * Mat4::translate(-self.center.extend(0.0))
+        let shake = self.trauma * self.trauma;
+        let offset = vec2(noise(0.0, self.shake_time), noise(10.0, self.shake_time))
+            * (shake * MAX_SHAKE_OFFSET * self.fov);
+        let rotation = noise(20.0, self.shake_time) * shake * MAX_SHAKE_ROTATION;
+        Mat4::scale_uniform(1.0 / self.fov)
+            * Mat4::rotate_z(-self.rotation - rotation)
+            * Mat4::translate(-(self.center + offset).extend(0.0))
     }
     fn projection_matrix(&self, framebuffer_size: Vec2<f32>) -> Mat4<f32> {
         Mat4::scale(vec3(
@@ -37,9 +158,11 @@ impl Camera {
             u_view_matrix: self.view_matrix(),
         }
     }
+    pub fn view_projection_matrix(&self, framebuffer_size: Vec2<f32>) -> Mat4<f32> {
+        self.projection_matrix(framebuffer_size) * self.view_matrix()
+    }
     pub fn world_to_screen(&self, framebuffer_size: Vec2<f32>, pos: Vec2<f32>) -> Vec2<f32> {
-        let pos = (self.projection_matrix(framebuffer_size) * self.view_matrix())
-            * pos.extend(0.0).extend(1.0);
+        let pos = self.view_projection_matrix(framebuffer_size) * pos.extend(0.0).extend(1.0);
         vec2(
             (pos.x + 1.0) / 2.0 * framebuffer_size.x,
             (pos.y + 1.0) / 2.0 * framebuffer_size.y,
@@ -50,8 +173,57 @@ impl Camera {
             pos.x / framebuffer_size.x * 2.0 - 1.0,
             pos.y / framebuffer_size.y * 2.0 - 1.0,
         );
-        let pos = (self.projection_matrix(framebuffer_size) * self.view_matrix()).inverse()
-            * pos.extend(0.0).extend(1.0);
+        let pos =
+            self.view_projection_matrix(framebuffer_size).inverse() * pos.extend(0.0).extend(1.0);
         pos.xy()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(steps: usize, delta_time: f32) -> Camera {
+        let mut camera = Camera::new(20.0);
+        camera.target_position = vec2(30.0, -10.0);
+        camera.target_fov = 40.0;
+        camera.target_rotation = 1.0;
+        for _ in 0..steps {
+            camera.update(delta_time);
+        }
+        camera
+    }
+
+    fn assert_same(a: &Camera, b: &Camera) {
+        assert!((a.center - b.center).len() < 1e-3);
+        assert!((a.fov - b.fov).abs() < 1e-3);
+        assert!((a.rotation - b.rotation).abs() < 1e-4);
+    }
+
+    #[test]
+    fn converges_identically_at_different_rates() {
+        let slow = run(30, 1.0 / 30.0);
+        let fast = run(144, 1.0 / 144.0);
+        assert_same(&slow, &fast);
+        assert!(slow.center.x > 0.0 && slow.center.x < 30.0);
+    }
+
+    #[test]
+    fn hitch_does_not_overshoot() {
+        let hitch = run(1, 0.5);
+        let smooth = run(500, 0.001);
+        assert_same(&hitch, &smooth);
+        let settled = run(1, 10.0);
+        assert!((settled.center - vec2(30.0, -10.0)).len() < 1e-2);
+        assert!(settled.fov <= 40.0);
+    }
+
+    #[test]
+    fn rotation_takes_shortest_path() {
+        let mut camera = Camera::new(20.0);
+        camera.rotation = 3.0;
+        camera.target_rotation = -3.0;
+        camera.update(0.1);
+        assert!(camera.rotation > 3.0);
+    }
+}
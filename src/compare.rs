@@ -0,0 +1,253 @@
+use super::*;
+
+const SECTORS: usize = 3;
+const CHECKPOINTS: usize = 10;
+const IMAGE_SIZE: usize = 1024;
+const GAIN_COLOR: Color<f32> = Color {
+    r: 0.0,
+    g: 0.7,
+    b: 0.0,
+    a: 1.0,
+};
+const LOSS_COLOR: Color<f32> = Color {
+    r: 0.9,
+    g: 0.0,
+    b: 0.0,
+    a: 1.0,
+};
+
+struct Lap {
+    times: Vec<f32>,
+    positions: Vec<Vec2<f32>>,
+    distances: Vec<f32>,
+    length: f32,
+    penalty: f32,
+    valid: bool,
+}
+
+impl Lap {
+    fn new(samples: &[Sample], centerline: &Centerline) -> Self {
+        let start = samples[0].time;
+        let mut progress = Progress::new(centerline, samples[0].pos);
+        let start_progress = progress.value;
+        let mut distances = Vec::with_capacity(samples.len());
+        for sample in samples {
+            progress.update(centerline, sample.pos);
+            distances.push((progress.value - start_progress) * centerline.length());
+        }
+        // The last sample already belongs to the next lap.
+        let last = &samples[samples.len() - 2];
+        Self {
+            times: samples.iter().map(|sample| sample.time - start).collect(),
+            positions: samples.iter().map(|sample| sample.pos).collect(),
+            distances,
+            length: centerline.length(),
+            penalty: last.penalty,
+            valid: last.valid,
+        }
+    }
+    fn best(telemetry: &Telemetry, centerline: &Centerline) -> Option<Self> {
+        let samples = &telemetry.samples;
+        let mut max_lap = samples.first()?.lap;
+        let mut starts = Vec::new();
+        for (i, sample) in samples.iter().enumerate() {
+            if sample.lap > max_lap {
+                max_lap = sample.lap;
+                starts.push(i);
+            }
+        }
+        starts
+            .windows(2)
+            .map(|starts| Self::new(&samples[starts[0]..=starts[1]], centerline))
+            .filter(|lap| lap.valid)
+            .min_by(|a, b| a.total_time().partial_cmp(&b.total_time()).unwrap())
+    }
+    fn time(&self) -> f32 {
+        *self.times.last().unwrap()
+    }
+    fn total_time(&self) -> f32 {
+        self.time() + self.penalty
+    }
+    fn at_distance(&self, distance: f32) -> (usize, f32) {
+        let i = match self.distances.iter().position(|&d| d >= distance) {
+            Some(0) => return (0, 0.0),
+            Some(i) => i,
+            None => return (self.times.len() - 1, self.time()),
+        };
+        let (d0, d1) = (self.distances[i - 1], self.distances[i]);
+        let t = if d1 > d0 {
+            (distance - d0) / (d1 - d0)
+        } else {
+            0.0
+        };
+        (
+            i,
+            self.times[i - 1] + (self.times[i] - self.times[i - 1]) * t,
+        )
+    }
+    fn split(&self, other: &Self, count: usize) -> Vec<(f32, f32, usize)> {
+        (1..=count)
+            .map(|i| {
+                if i == count {
+                    return (self.time(), other.time(), other.times.len() - 1);
+                }
+                let distance = self.length * i as f32 / count as f32;
+                let (_, time) = self.at_distance(distance);
+                let (index, other_time) = other.at_distance(distance);
+                (time, other_time, index)
+            })
+            .collect()
+    }
+    fn describe(&self) -> String {
+        if self.penalty > 0.0 {
+            format!("{:.2}s (+{:.1}s penalty)", self.total_time(), self.penalty)
+        } else {
+            format!("{:.2}s", self.time())
+        }
+    }
+}
+
+fn load(path: &std::path::Path, track: &Track, centerline: &Centerline) -> Result<Lap, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let telemetry: Telemetry = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    if telemetry.track != track.id() {
+        return Err(format!(
+            "{} was not recorded on the current track.json",
+            path.display()
+        ));
+    }
+    Lap::best(&telemetry, centerline)
+        .ok_or_else(|| format!("No complete valid lap in {}", path.display()))
+}
+
+fn draw_trajectory(batch: &mut Batch, positions: &[Vec2<f32>], color: Color<f32>) {
+    for segment in positions.windows(2) {
+        batch.line(segment[0], segment[1], 0.4, color);
+    }
+}
+
+pub fn run(args: &[String], cwd: &std::path::Path) -> Result<(), String> {
+    if args.len() < 2 || args.len() > 3 {
+        return Err("Usage: compare <a.json> <b.json> [output.png]".to_owned());
+    }
+    let track = Track::parse(&std::fs::read_to_string("track.json").map_err(|e| e.to_string())?);
+    let centerline = Centerline::from_track(&track);
+    let a = load(&cwd.join(&args[0]), &track, &centerline)?;
+    let b = load(&cwd.join(&args[1]), &track, &centerline)?;
+    println!("LAP A: {} ({})", a.describe(), args[0]);
+    println!("LAP B: {} ({})", b.describe(), args[1]);
+
+    println!();
+    println!("{:<8}{:>8}{:>8}{:>8}", "SECTOR", "A", "B", "DIFF");
+    let mut last = (0.0, 0.0);
+    for (i, &(time_a, time_b, _)) in a.split(&b, SECTORS).iter().enumerate() {
+        let (sector_a, sector_b) = (time_a - last.0, time_b - last.1);
+        println!(
+            "{:<8}{:>8.2}{:>8.2}{:>+8.2}",
+            i + 1,
+            sector_a,
+            sector_b,
+            sector_b - sector_a
+        );
+        last = (time_a, time_b);
+    }
+
+    println!();
+    println!("{:<8}{:>8}", "DIST", "DELTA");
+    let checkpoints = a.split(&b, CHECKPOINTS);
+    let mut last_delta = 0.0;
+    for (i, &(time_a, time_b, _)) in checkpoints.iter().enumerate() {
+        let delta = time_b - time_a;
+        println!(
+            "{:<8}{:>+8.2}{:>8}",
+            format!("{}%", (i + 1) * 100 / CHECKPOINTS),
+            delta,
+            if delta < last_delta { "GAIN" } else { "LOSS" }
+        );
+        last_delta = delta;
+    }
+
+    let output = cwd.join(args.get(2).map_or("compare.png", |path| path.as_str()));
+    let themes = Themes::parse(&std::fs::read_to_string("themes.json").map_err(|e| e.to_string())?);
+    let theme = themes.get(track.theme.as_deref());
+    let mut batch = Batch::new();
+    draw_track(&mut batch, &track, theme, &[], 0.0);
+    draw_trajectory(&mut batch, &a.positions, theme.hint);
+    let mut from = (0, 0.0);
+    for &(time_a, time_b, index) in &checkpoints {
+        let delta = time_b - time_a;
+        let color = if delta < from.1 {
+            GAIN_COLOR
+        } else {
+            LOSS_COLOR
+        };
+        draw_trajectory(&mut batch, &b.positions[from.0..=index], color);
+        from = (index, delta);
+    }
+    let (min, max) = track.bounds();
+    let size = vec2(IMAGE_SIZE, IMAGE_SIZE);
+    let camera = Camera::fit(
+        min,
+        max,
+        &Viewport::full(size.map(|x| x as f32)),
+        size.map(|x| x as f32),
+    );
+    software::render(
+        size,
+        &camera,
+        theme.background,
+        Settings::default().edge_softness(),
+        &batch,
+    )
+    .save(&output)
+    .map_err(|e| format!("Failed to save {}: {}", output.display(), e))?;
+    println!();
+    println!("Saved trajectory overlay to {}", output.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(time: f32, lap: i32, penalty: f32, valid: bool) -> Sample {
+        Sample {
+            time,
+            lap,
+            penalty,
+            valid,
+            pos: vec2(0.0, 0.0),
+            vel: vec2(0.0, 0.0),
+            rotation: 0.0,
+            w: 0.0,
+            left_thruster: false,
+            right_thruster: false,
+            collisions: 0,
+        }
+    }
+
+    #[test]
+    fn best_lap_counts_penalties_and_skips_recounts() {
+        let centerline = Centerline::new(vec![vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 10.0)]);
+        let telemetry = Telemetry {
+            track: 0,
+            samples: vec![
+                sample(0.0, 0, 0.0, true),
+                sample(1.0, 1, 0.0, true),
+                sample(5.0, 1, 0.0, false),
+                sample(6.0, 2, 0.0, true),
+                sample(9.0, 2, 3.0, true),
+                sample(10.0, 3, 0.0, true),
+                sample(11.0, 2, 0.0, true),
+                sample(12.0, 3, 0.0, true),
+                sample(16.0, 3, 0.0, true),
+                sample(16.5, 4, 0.0, true),
+            ],
+        };
+        let best = Lap::best(&telemetry, &centerline).unwrap();
+        assert_eq!(best.total_time(), 6.5);
+        assert_eq!(best.times.len(), 5);
+    }
+}
@@ -0,0 +1,176 @@
+use super::*;
+
+use std::collections::VecDeque;
+
+const VELOCITY_SCALE: f32 = 0.3;
+const SPIN_SCALE: f32 = 0.3;
+const COLLIDER_COLOR: Color<f32> = Color {
+    r: 1.0,
+    g: 0.0,
+    b: 1.0,
+    a: 1.0,
+};
+const VELOCITY_COLOR: Color<f32> = Color {
+    r: 0.0,
+    g: 0.8,
+    b: 0.0,
+    a: 1.0,
+};
+
+pub fn draw_player_debug(batch: &mut Batch, player: &Player) {
+    for circle in &[
+        player.head(),
+        player.left_thruster(),
+        player.right_thruster(),
+    ] {
+        batch.circle(circle.pos, circle.r - 0.05, circle.r + 0.05, COLLIDER_COLOR);
+    }
+    if player.vel.len() > 1.0 {
+        draw_arrow(
+            batch,
+            player.pos,
+            player.pos + player.vel * VELOCITY_SCALE,
+            VELOCITY_COLOR,
+        );
+    }
+    batch.arc(
+        player.pos,
+        2.0,
+        0.15,
+        player.rotation..player.rotation + player.w * SPIN_SCALE,
+        VELOCITY_COLOR,
+    );
+}
+
+const FRAME_HISTORY: usize = 120;
+const TARGET_FRAME_TIME: f32 = 1.0 / 60.0;
+const IMPULSE_SCALE: f32 = 0.3;
+const FORCE_SCALE: f32 = 0.1;
+const CONTACT_COLOR: Color<f32> = Color {
+    r: 1.0,
+    g: 0.0,
+    b: 0.0,
+    a: 1.0,
+};
+const IMPULSE_COLOR: Color<f32> = Color {
+    r: 1.0,
+    g: 0.6,
+    b: 0.0,
+    a: 1.0,
+};
+const FORCE_COLOR: Color<f32> = Color {
+    r: 1.0,
+    g: 1.0,
+    b: 0.0,
+    a: 1.0,
+};
+const QUERY_COLOR: Color<f32> = Color {
+    r: 0.0,
+    g: 0.6,
+    b: 1.0,
+    a: 0.6,
+};
+
+#[derive(Default)]
+pub struct PhysicsDebug {
+    pub contacts: Vec<Collision>,
+    pub impulses: Vec<(Vec2<f32>, Vec2<f32>)>,
+    pub forces: Vec<(Vec2<f32>, Vec2<f32>)>,
+    frame_times: VecDeque<f32>,
+}
+
+impl PhysicsDebug {
+    pub fn begin_frame(&mut self, delta_time: f32) {
+        self.contacts.clear();
+        self.impulses.clear();
+        self.forces.clear();
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(delta_time);
+    }
+    pub fn draw_world(&self, batch: &mut Batch, track: &Track) {
+        let regions = track
+            .hazards
+            .iter()
+            .map(|hazard| (hazard.pos, hazard.r))
+            .chain(track.boost_pads.iter().map(|pad| (pad.pos, pad.r)))
+            .chain(track.orbs.iter().map(|orb| (orb.pos, orb.r)))
+            .chain(track.infield.iter().map(|zone| (zone.pos, zone.r)));
+        for (pos, r) in regions {
+            batch.circle(pos, r - 0.05, r + 0.05, QUERY_COLOR);
+        }
+        for contact in &self.contacts {
+            batch.circle(contact.pos, 0.0, 0.2, CONTACT_COLOR);
+            batch.line(
+                contact.pos,
+                contact.pos - contact.normal * contact.penetration,
+                0.3,
+                CONTACT_COLOR,
+            );
+            draw_arrow(
+                batch,
+                contact.pos,
+                contact.pos + contact.normal * 1.5,
+                CONTACT_COLOR,
+            );
+        }
+        for &(pos, impulse) in &self.impulses {
+            let arrow = impulse * IMPULSE_SCALE;
+            if arrow.len() > 0.1 {
+                draw_arrow(batch, pos, pos + arrow, IMPULSE_COLOR);
+            }
+        }
+        for &(pos, force) in &self.forces {
+            let arrow = force * FORCE_SCALE;
+            if arrow.len() > 0.1 {
+                draw_arrow(batch, pos, pos + arrow, FORCE_COLOR);
+            }
+        }
+    }
+    pub fn draw_graph(
+        &self,
+        geng: &Geng,
+        framebuffer: &mut ugli::Framebuffer,
+        font: &geng::Font,
+        theme: &Theme,
+        pos: Vec2<f32>,
+        size: Vec2<f32>,
+    ) {
+        geng.draw_2d()
+            .quad(framebuffer, AABB::pos_size(pos, size), theme.panel);
+        let width = size.x / FRAME_HISTORY as f32;
+        for (i, &frame_time) in self.frame_times.iter().enumerate() {
+            let height = (frame_time / (TARGET_FRAME_TIME * 2.0)).min(1.0) * size.y;
+            geng.draw_2d().quad(
+                framebuffer,
+                AABB::pos_size(pos + vec2(i as f32 * width, 0.0), vec2(width, height)),
+                if frame_time > TARGET_FRAME_TIME * 1.1 {
+                    theme.beam
+                } else {
+                    theme.hint
+                },
+            );
+        }
+        geng.draw_2d().quad(
+            framebuffer,
+            AABB::pos_size(pos + vec2(0.0, size.y / 2.0), vec2(size.x, 1.0)),
+            theme.text,
+        );
+        if self.frame_times.is_empty() {
+            return;
+        }
+        let average = self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32;
+        font.draw(
+            framebuffer,
+            &format!(
+                "FPS: {:.0} ({:.1} ms)",
+                1.0 / average.max(1e-6),
+                average * 1000.0
+            ),
+            pos + vec2(0.0, size.y),
+            size.y / 3.0,
+            theme.text,
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use super::*;
+
+const TIRE_SIZE: f32 = 1.0;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tool {
+    Tire,
+    Wall,
+    StartLine,
+    Spawn,
+    Centerline,
+}
+
+impl Tool {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Tire => "TIRE",
+            Self::Wall => "WALL",
+            Self::StartLine => "START LINE",
+            Self::Spawn => "SPAWN",
+            Self::Centerline => "CENTERLINE",
+        }
+    }
+}
+
+enum Drag {
+    Tire(usize),
+    Wall(Vec<Vec2<f32>>),
+    StartLine(Vec2<f32>),
+    Spawn,
+    Centerline(Vec<Vec2<f32>>),
+    Pan,
+}
+
+pub struct Editor {
+    pub camera: Camera,
+    tool: Tool,
+    drag: Option<Drag>,
+    mouse_pos: Vec2<f32>,
+    message: Option<String>,
+}
+
+fn tire_at(track: &Track, pos: Vec2<f32>) -> Option<usize> {
+    track
+        .tires
+        .iter()
+        .rposition(|tire| (tire.pos - pos).len() < tire.r)
+}
+
+fn fill_path(path: &[Vec2<f32>]) -> Vec<Circle> {
+    let mut result = Vec::new();
+    let mut next = 0.0;
+    for segment in path.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let len = (b - a).len();
+        let mut dist = next;
+        while dist <= len {
+            result.push(Circle {
+                pos: a + (b - a) * (dist / len),
+                r: TIRE_SIZE,
+            });
+            dist += 2.0 * TIRE_SIZE;
+        }
+        next = dist - len;
+    }
+    if result.is_empty() {
+        if let Some(&pos) = path.first() {
+            result.push(Circle { pos, r: TIRE_SIZE });
+        }
+    }
+    result
+}
+
+pub fn draw_arrow(batch: &mut Batch, from: Vec2<f32>, to: Vec2<f32>, color: Color<f32>) {
+    let dir = (to - from).normalize();
+    let normal = vec2(-dir.y, dir.x);
+    batch.line(from, to - dir * 0.6, 0.2, color);
+    batch.polygon(
+        &[
+            to,
+            to - dir * 0.8 + normal * 0.5,
+            to - dir * 0.8 - normal * 0.5,
+        ],
+        color,
+    );
+}
+
+impl Editor {
+    pub fn new(game_camera: &Camera) -> Self {
+        let mut camera = Camera::new(game_camera.fov);
+        camera.center = game_camera.center;
+        camera.target_position = game_camera.center;
+        Self {
+            camera,
+            tool: Tool::Tire,
+            drag: None,
+            mouse_pos: game_camera.center,
+            message: None,
+        }
+    }
+    fn save(&mut self, track: &Track) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let json = serde_json::to_string_pretty(track).expect("Failed to serialize track");
+            self.message = Some(match std::fs::write("track.json", json) {
+                Ok(()) => "TRACK SAVED".to_owned(),
+                Err(e) => {
+                    error!("Failed to save track: {}", e);
+                    "FAILED TO SAVE TRACK".to_owned()
+                }
+            });
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = track;
+            self.message = Some("SAVING IS NOT SUPPORTED ON THE WEB".to_owned());
+        }
+    }
+    pub fn handle_event(
+        &mut self,
+        track: &mut Track,
+        event: &geng::Event,
+        framebuffer_size: Vec2<f32>,
+    ) {
+        match *event {
+            geng::Event::KeyDown { key } => match key {
+                geng::Key::Num1 => self.tool = Tool::Tire,
+                geng::Key::Num2 => self.tool = Tool::Wall,
+                geng::Key::Num3 => self.tool = Tool::StartLine,
+                geng::Key::Num4 => self.tool = Tool::Spawn,
+                geng::Key::Num5 => self.tool = Tool::Centerline,
+                geng::Key::S => self.save(track),
+                _ => {}
+            },
+            geng::Event::MouseDown { position, button } => {
+                if self.drag.is_some() {
+                    return;
+                }
+                let pos = self
+                    .camera
+                    .screen_to_world(framebuffer_size, position.map(|x| x as f32));
+                self.mouse_pos = pos;
+                self.message = None;
+                match button {
+                    geng::MouseButton::Left => {
+                        self.drag = Some(match self.tool {
+                            Tool::Tire => match tire_at(track, pos) {
+                                Some(index) => Drag::Tire(index),
+                                None => {
+                                    track.tires.push(Circle { pos, r: TIRE_SIZE });
+                                    Drag::Tire(track.tires.len() - 1)
+                                }
+                            },
+                            Tool::Wall => Drag::Wall(vec![pos]),
+                            Tool::StartLine => Drag::StartLine(pos),
+                            Tool::Spawn => {
+                                track.spawn = pos;
+                                Drag::Spawn
+                            }
+                            Tool::Centerline => Drag::Centerline(vec![pos]),
+                        })
+                    }
+                    geng::MouseButton::Right => match tire_at(track, pos) {
+                        Some(index) => {
+                            track.tires.remove(index);
+                        }
+                        None => self.drag = Some(Drag::Pan),
+                    },
+                    geng::MouseButton::Middle => self.drag = Some(Drag::Pan),
+                }
+            }
+            geng::Event::MouseMove { position, .. } => {
+                let mut pos = self
+                    .camera
+                    .screen_to_world(framebuffer_size, position.map(|x| x as f32));
+                match &mut self.drag {
+                    Some(Drag::Tire(index)) => track.tires[*index].pos = pos,
+                    Some(Drag::Wall(path)) | Some(Drag::Centerline(path)) => {
+                        if (pos - *path.last().unwrap()).len() > TIRE_SIZE {
+                            path.push(pos);
+                        }
+                    }
+                    Some(Drag::Spawn) => {
+                        if (pos - track.spawn).len() > TIRE_SIZE {
+                            track.spawn_rotation = (pos - track.spawn).arg();
+                        }
+                    }
+                    Some(Drag::Pan) => {
+                        let delta = self.mouse_pos - pos;
+                        self.camera.center += delta;
+                        self.camera.target_position = self.camera.center;
+                        pos = self.mouse_pos;
+                    }
+                    Some(Drag::StartLine(_)) | None => {}
+                }
+                self.mouse_pos = pos;
+            }
+            geng::Event::MouseUp { .. } => match self.drag.take() {
+                Some(Drag::Wall(mut path)) => {
+                    path.push(self.mouse_pos);
+                    track.tires.extend(fill_path(&path));
+                }
+                Some(Drag::Centerline(mut path)) => {
+                    path.push(self.mouse_pos);
+                    // A click without a drag clears the centerline so it is
+                    // derived from the walls again.
+                    track.centerline = if path.len() > 2 { path } else { Vec::new() };
+                }
+                Some(Drag::StartLine(start)) if (self.mouse_pos - start).len() > TIRE_SIZE => {
+                    track.start_line = [start, self.mouse_pos];
+                }
+                _ => {}
+            },
+            geng::Event::Wheel { delta } => {
+                if delta == 0.0 {
+                    return;
+                }
+                let old_fov = self.camera.fov;
+                self.camera.fov = (old_fov * 1.1f32.powf(-delta.signum() as f32)).clamp(5.0, 200.0);
+                self.camera.target_fov = self.camera.fov;
+                self.camera.center = self.mouse_pos
+                    + (self.camera.center - self.mouse_pos) * (self.camera.fov / old_fov);
+                self.camera.target_position = self.camera.center;
+            }
+            _ => {}
+        }
+    }
+    pub fn draw(&self, renderer: &Renderer, framebuffer: &mut ugli::Framebuffer, track: &Track) {
+        let mut batch = Batch::new();
+        let highlight = Color::rgb(1.0, 0.0, 0.0);
+        if self.tool == Tool::Tire && self.drag.is_none() {
+            if let Some(index) = tire_at(track, self.mouse_pos) {
+                let tire = &track.tires[index];
+                batch.circle(tire.pos, tire.r - 0.15, tire.r + 0.15, highlight);
+            }
+        }
+        if let Some(Drag::Wall(path)) = &self.drag {
+            let mut path = path.clone();
+            path.push(self.mouse_pos);
+            for tire in fill_path(&path) {
+                batch.circle(tire.pos, tire.r - 0.1, tire.r + 0.1, highlight);
+            }
+        }
+
+        let centerline_color = Color::rgb(0.0, 0.4, 1.0);
+        let centerline = match &self.drag {
+            Some(Drag::Centerline(path)) => {
+                let mut path = path.clone();
+                path.push(self.mouse_pos);
+                path
+            }
+            _ => track.centerline.clone(),
+        };
+        for (i, &point) in centerline.iter().enumerate() {
+            let next = centerline[(i + 1) % centerline.len()];
+            batch.line(point, next, 0.2, centerline_color);
+        }
+
+        let [a, b] = match self.drag {
+            Some(Drag::StartLine(start)) => [start, self.mouse_pos],
+            _ => track.start_line,
+        };
+        batch.line(a, b, 0.3, highlight);
+        let forward = vec2(a.y - b.y, b.x - a.x).normalize() * 3.0;
+        draw_arrow(
+            &mut batch,
+            (a + b) / 2.0,
+            (a + b) / 2.0 + forward,
+            highlight,
+        );
+
+        batch.circle(track.spawn, 0.9, 1.1, Color::rgb(0.0, 0.6, 0.0));
+        draw_arrow(
+            &mut batch,
+            track.spawn,
+            track.spawn + Vec2::rotated(vec2(3.0, 0.0), track.spawn_rotation),
+            Color::rgb(0.0, 0.6, 0.0),
+        );
+
+        renderer.draw_batch(framebuffer, &self.camera, &batch);
+    }
+    pub fn draw_hud(&self, framebuffer: &mut ugli::Framebuffer, font: &geng::Font, theme: &Theme) {
+        let framebuffer_size = framebuffer.size().map(|x| x as f32);
+        let font_size = framebuffer_size.y / 20.0;
+        font.draw(
+            framebuffer,
+            &format!("EDITOR - TOOL: {}", self.tool.name()),
+            vec2(5.0, framebuffer_size.y - font_size - 5.0),
+            font_size,
+            theme.text,
+        );
+        font.draw(
+            framebuffer,
+            "1 TIRE  2 WALL  3 START LINE  4 SPAWN  5 CENTERLINE",
+            vec2(5.0, framebuffer_size.y - font_size * 1.7 - 5.0),
+            font_size * 0.6,
+            theme.hint,
+        );
+        font.draw(
+            framebuffer,
+            "RMB DELETE/PAN  WHEEL ZOOM  S SAVE  E PLAY",
+            vec2(5.0, framebuffer_size.y - font_size * 2.3 - 5.0),
+            font_size * 0.6,
+            theme.hint,
+        );
+        if let Some(message) = &self.message {
+            font.draw(framebuffer, message, vec2(5.0, 5.0), font_size, theme.text);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use super::*;
+
+const GHOST_ALPHA: f32 = 0.4;
+
+pub struct Ghost {
+    samples: Vec<Sample>,
+    progress: Vec<f32>,
+    reached: Vec<f32>,
+    style: ShipStyle,
+    player: Option<Player>,
+    particles: ParticleSystem,
+    exhaust: ExhaustCache,
+    exhaust_timer: EmitTimer,
+}
+
+impl Ghost {
+    pub fn new(samples: Vec<Sample>, style: ShipStyle, centerline: &Centerline) -> Self {
+        let mut progress = Vec::with_capacity(samples.len());
+        let mut reached = Vec::with_capacity(samples.len());
+        if let Some(first) = samples.first() {
+            let mut tracker = Progress::new(centerline, first.pos);
+            let start = tracker.value;
+            for sample in &samples {
+                tracker.update(centerline, sample.pos);
+                let value = tracker.value - start;
+                progress.push(value);
+                reached.push(reached.last().map_or(value, |&max: &f32| max.max(value)));
+            }
+        }
+        Self {
+            samples,
+            progress,
+            reached,
+            style,
+            player: None,
+            particles: ParticleSystem::new(MAX_PARTICLES),
+            exhaust: ExhaustCache::default(),
+            exhaust_timer: EmitTimer::default(),
+        }
+    }
+    fn sample(&self, time: f32) -> Option<Sample> {
+        let i = self.samples.partition_point(|sample| sample.time < time);
+        if i == 0 {
+            return self.samples.first().cloned();
+        }
+        let (a, b) = (&self.samples[i - 1], self.samples.get(i)?);
+        let t = (time - a.time) / (b.time - a.time).max(1e-6);
+        Some(Sample {
+            time,
+            pos: a.pos + (b.pos - a.pos) * t,
+            vel: a.vel + (b.vel - a.vel) * t,
+            rotation: a.rotation + (b.rotation - a.rotation) * t,
+            w: a.w + (b.w - a.w) * t,
+            ..a.clone()
+        })
+    }
+    /// Progress made since the start of the lap at the given lap time.
+    pub fn progress(&self, time: f32) -> Option<f32> {
+        let i = self.samples.partition_point(|sample| sample.time < time);
+        if i == 0 {
+            return self.progress.first().copied();
+        }
+        let (a, b) = (&self.samples[i - 1], self.samples.get(i)?);
+        let t = (time - a.time) / (b.time - a.time).max(1e-6);
+        Some(self.progress[i - 1] + (self.progress[i] - self.progress[i - 1]) * t)
+    }
+    /// How long ago in lap time the ghost first got as far as `progress`.
+    pub fn gap(&self, progress: f32, time: f32) -> f32 {
+        let i = self.reached.partition_point(|&reached| reached < progress);
+        self.samples.get(i).map_or(0.0, |sample| time - sample.time)
+    }
+    pub fn marker(&self, theme: &Theme) -> Option<Marker> {
+        self.player.as_ref().map(|player| Marker {
+            pos: player.pos,
+            rotation: player.rotation,
+            color: Color {
+                a: GHOST_ALPHA,
+                ..self.style.head.color(theme.head)
+            },
+        })
+    }
+    pub fn update(&mut self, time: f32, delta_time: f32, exhaust_color: Color<f32>) {
+        self.player = self.sample(time).map(|sample| {
+            let mut player = Player::new(sample.pos, sample.rotation);
+            player.vel = sample.vel;
+            player.w = sample.w;
+            let exhaust = self.exhaust.get(self.style.trail, exhaust_color);
+            let count = self.exhaust_timer.tick(delta_time, exhaust.rate);
+            let direction = (-Vec2::rotated(vec2(1.0, 0.0), player.rotation)).arg();
+            for (on, pos) in [
+                (sample.left_thruster, player.left_thruster_tube()),
+                (sample.right_thruster, player.right_thruster_tube()),
+            ] {
+                if on {
+                    self.particles
+                        .burst(&exhaust, pos, player.vel, direction, count);
+                }
+            }
+            player
+        });
+        self.particles.update(delta_time);
+    }
+    pub fn draw(&self, batch: &mut Batch, theme: &Theme, t: f32) {
+        if let Some(player) = &self.player {
+            let mut ghost = Batch::new();
+            draw_player(&mut ghost, player, &self.particles, &self.style, theme, t);
+            ghost.fade(GHOST_ALPHA);
+            batch.append(ghost);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(time: f32, x: f32) -> Sample {
+        Sample {
+            time,
+            lap: 0,
+            penalty: 0.0,
+            valid: true,
+            pos: vec2(x, 0.0),
+            vel: vec2(0.0, 0.0),
+            rotation: 0.0,
+            w: 0.0,
+            left_thruster: false,
+            right_thruster: false,
+            collisions: 0,
+        }
+    }
+
+    #[test]
+    fn replays_by_lap_time() {
+        let centerline = Centerline::new(vec![
+            vec2(0.0, 0.0),
+            vec2(40.0, 0.0),
+            vec2(40.0, 40.0),
+            vec2(0.0, 40.0),
+        ]);
+        let ghost = Ghost::new(
+            vec![sample(0.0, 0.0), sample(1.0, 10.0), sample(2.0, 30.0)],
+            ShipStyle::default(),
+            &centerline,
+        );
+        assert_eq!(ghost.sample(-1.0).unwrap().pos, vec2(0.0, 0.0));
+        assert!((ghost.sample(0.5).unwrap().pos.x - 5.0).abs() < 1e-4);
+        assert!((ghost.sample(1.5).unwrap().pos.x - 20.0).abs() < 1e-4);
+        assert!(ghost.sample(2.5).is_none());
+        assert!((ghost.progress(1.5).unwrap() - 20.0 / 160.0).abs() < 1e-4);
+        assert!((ghost.gap(10.0 / 160.0, 3.0) - 2.0).abs() < 1e-4);
+    }
+}
@@ -1,10 +1,39 @@
 use geng::prelude::*;
 
+mod audio;
 mod camera;
+mod compare;
+mod debug;
+mod editor;
+mod ghost;
+mod minimap;
+mod options;
+mod particles;
+mod progress;
+mod racing_line;
 mod renderer;
+mod settings;
+mod ship;
+mod telemetry;
+mod theme;
+mod track;
 
+use audio::*;
 use camera::*;
+use debug::*;
+use editor::*;
+use ghost::*;
+use minimap::*;
+use options::*;
+use particles::*;
+use progress::*;
+use racing_line::*;
 use renderer::*;
+use settings::*;
+use ship::*;
+use telemetry::*;
+use theme::*;
+use track::*;
 
 #[derive(geng::Assets)]
 struct Assets {
@@ -14,8 +43,13 @@ struct Assets {
     bump: geng::Sound,
     #[asset(path = "music.ogg")]
     music: geng::Sound,
+    #[asset(path = "track.json")]
+    track: String,
+    #[asset(path = "themes.json")]
+    themes: String,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
 struct Circle {
     pub pos: Vec2<f32>,
     pub r: f32,
@@ -44,6 +78,22 @@ impl Circle {
     }
 }
 
+struct ForceField {
+    pub force: Vec2<f32>,
+    pub torque: f32,
+    pub damping: f32,
+}
+
+impl Default for ForceField {
+    fn default() -> Self {
+        Self {
+            force: vec2(0.0, 0.0),
+            torque: 0.0,
+            damping: 1.0,
+        }
+    }
+}
+
 struct Player {
     pub pos: Vec2<f32>,
     pub vel: Vec2<f32>,
@@ -52,18 +102,21 @@ struct Player {
 }
 
 impl Player {
-    pub fn new(pos: Vec2<f32>) -> Self {
+    pub fn new(pos: Vec2<f32>, rotation: f32) -> Self {
         Self {
             pos,
             vel: vec2(0.0, 0.0),
-            rotation: f32::PI / 2.0,
+            rotation,
             w: 0.0,
         }
     }
-    pub fn update(&mut self, delta_time: f32) {
+    pub fn update(&mut self, delta_time: f32, field: &ForceField) {
         const DAMP: f32 = 0.9;
-        self.vel -= self.vel * DAMP * delta_time.min(1.0);
-        self.w -= self.w * DAMP * delta_time.min(1.0);
+        self.vel += field.force * delta_time;
+        self.w += field.torque * delta_time;
+        let damp = DAMP * field.damping;
+        self.vel -= self.vel * damp * delta_time.min(1.0);
+        self.w -= self.w * damp * delta_time.min(1.0);
         self.pos += self.vel * delta_time;
         self.rotation += self.w * delta_time;
     }
@@ -109,79 +162,162 @@ impl Player {
     }
 }
 
-struct Particle {
-    pub pos: Vec2<f32>,
-    pub r: f32,
-    pub color: Color<f32>,
-    pub vel: Vec2<f32>,
-    pub life: f32,
+#[derive(Clone, Copy, PartialEq)]
+enum PenaltyReason {
+    TireHit,
+    Cut,
 }
 
-impl Particle {
-    pub fn update(&mut self, delta_time: f32) {
-        self.pos += self.vel * delta_time;
-        self.life -= delta_time;
+impl PenaltyReason {
+    fn name(self) -> &'static str {
+        match self {
+            Self::TireHit => "TIRE HIT",
+            Self::Cut => "CUT",
+        }
     }
 }
 
+fn penalty_text(penalty: Penalty) -> String {
+    match penalty {
+        Penalty::Time(seconds) => format!("+{:.1}s", seconds),
+        Penalty::InvalidateLap => "LAP INVALID".to_owned(),
+    }
+}
+
+fn total_penalty(penalties: &[(PenaltyReason, Penalty)]) -> (f32, bool) {
+    let mut time = 0.0;
+    let mut valid = true;
+    for &(_, penalty) in penalties {
+        match penalty {
+            Penalty::Time(seconds) => time += seconds,
+            Penalty::InvalidateLap => valid = false,
+        }
+    }
+    (time, valid)
+}
+
+struct LapResult {
+    time: f32,
+    valid: bool,
+    penalties: Vec<(PenaltyReason, Penalty)>,
+}
+
 struct Game {
     t: f32,
     assets: Assets,
-    next_thruster_particle: f32,
+    exhaust_timer: EmitTimer,
     geng: Rc<Geng>,
     renderer: Rc<Renderer>,
+    offscreen: Offscreen,
+    settings: AutoSave<Settings>,
+    themes: Themes,
     camera: Camera,
-    obstacles: Vec<Circle>,
+    cinematic_spot: Option<usize>,
+    editor: Option<Editor>,
+    minimap: Minimap,
+    options: Option<OptionsMenu>,
+    track: Track,
     player: Player,
-    particles: Vec<Particle>,
+    centerline: Centerline,
+    progress: Progress,
+    racing_line: RacingLine,
+    progress_history: ProgressHistory,
+    wrong_way_timer: f32,
+    thrusters: [bool; 2],
+    throttle: f32,
+    debug: bool,
+    physics_debug: PhysicsDebug,
+    telemetry: Telemetry,
+    lap_samples: Vec<Sample>,
+    lap_start: f32,
+    lap_start_progress: f32,
+    ghost: Option<Ghost>,
+    particles: ParticleSystem,
+    decals: Decals,
+    sparks: Rc<Emitter>,
+    confetti: Rc<Emitter>,
+    exhaust: ExhaustCache,
     background: Vec<Vec2<f32>>,
     font: geng::Font,
     laps_done: i32,
+    max_laps_done: i32,
     current_lap_timer: Timer,
     best_lap_time: Option<f32>,
+    lap_penalties: Vec<(PenaltyReason, Penalty)>,
+    last_lap: Option<LapResult>,
+    touching_tires: bool,
+    cutting: bool,
+    boost_pad_contacts: Vec<bool>,
+    collected_orbs: Vec<bool>,
+    score: i32,
     thruster_effect: Option<geng::SoundEffect>,
     music_effect: Option<geng::SoundEffect>,
+    focused: bool,
+    data_dir: std::path::PathBuf,
 }
 
+const WRONG_WAY_DELAY: f32 = 0.5;
+const THRUSTER_VOLUME: f64 = 0.3;
+const MUSIC_VOLUME: f64 = 0.3;
+const BUMP_VOLUME: f64 = 0.3;
+const THROTTLE_RESPONSE: f32 = 10.0;
+const SPEED_BLUR_TIME: f32 = 0.03;
+const SPEED_BLUR_MIN: f32 = 10.0;
+
 const INNER: f32 = 55.0;
 const OUTER: f32 = 70.0;
 
 impl Game {
-    pub fn new(geng: &Rc<Geng>, mut assets: Assets) -> Self {
+    pub fn new(geng: &Rc<Geng>, mut assets: Assets, data_dir: std::path::PathBuf) -> Self {
         assets.thruster.looped = true;
         assets.music.looped = true;
+        let track = Track::parse(&assets.track);
+        let themes = Themes::parse(&assets.themes);
+        let mut decals = Decals::new(geng);
+        let (min, max) = track.bounds();
+        decals.reset(min, max);
+        let renderer = Rc::new(Renderer::new(geng));
+        let centerline = Centerline::from_track(&track);
+        let mut settings: AutoSave<Settings> =
+            AutoSave::load(&data_dir.join("settings.json").to_string_lossy());
+        settings.pixel_height = settings.pixel_height.max(1);
         Self {
             music_effect: None,
+            focused: true,
+            data_dir,
             t: 0.0,
             assets,
             geng: geng.clone(),
-            renderer: Rc::new(Renderer::new(geng)),
+            minimap: Minimap::new(geng, &renderer),
+            renderer,
+            offscreen: Offscreen::new(geng),
+            settings,
+            themes,
             camera: Camera::new(20.0),
-            obstacles: {
-                let mut result = Vec::new();
-                let tire_size = 1.0;
-                let noise = noise::OpenSimplex::new();
-                let mut add_circle = |r: f32| {
-                    let mut angle = 0.0;
-                    while angle < 2.0 * f32::PI {
-                        let r = r
-                            * (1.0
-                                + noise::NoiseFn::get(&noise, [angle as f64 * 10.0, 0.0]) as f32
-                                    * 0.1);
-                        result.push(Circle {
-                            pos: Vec2::rotated(vec2(r, 0.0), angle),
-                            r: tire_size,
-                        });
-                        angle += 2.0 * tire_size / r;
-                    }
-                };
-                add_circle(INNER);
-                add_circle(OUTER);
-                result
-            },
-            player: Player::new(vec2((INNER + OUTER) / 2.0, 0.0)),
-            particles: Vec::new(),
-            next_thruster_particle: 0.0,
+            cinematic_spot: None,
+            editor: None,
+            options: None,
+            player: Player::new(track.spawn, track.spawn_rotation),
+            progress: Progress::new(&centerline, track.spawn),
+            racing_line: RacingLine::compute(&track, &centerline),
+            centerline,
+            progress_history: ProgressHistory::default(),
+            wrong_way_timer: 0.0,
+            thrusters: [false; 2],
+            throttle: 0.0,
+            debug: false,
+            physics_debug: PhysicsDebug::default(),
+            telemetry: Telemetry::new(&track),
+            lap_samples: Vec::new(),
+            lap_start: 0.0,
+            lap_start_progress: 0.0,
+            ghost: None,
+            particles: ParticleSystem::new(MAX_PARTICLES),
+            decals,
+            sparks: Rc::new(Emitter::sparks()),
+            confetti: Rc::new(Emitter::confetti()),
+            exhaust: ExhaustCache::default(),
+            exhaust_timer: EmitTimer::default(),
             background: {
                 let mut result = Vec::new();
                 let r = (INNER + OUTER) / 2.0;
@@ -201,232 +337,464 @@ impl Game {
             },
             font: geng::Font::new(geng, include_bytes!("PixelEmulator-xq08.ttf").to_vec()).unwrap(),
             laps_done: 0,
+            max_laps_done: 0,
             best_lap_time: None,
+            lap_penalties: Vec::new(),
+            last_lap: None,
+            touching_tires: false,
+            cutting: false,
+            boost_pad_contacts: vec![false; track.boost_pads.len()],
+            collected_orbs: vec![false; track.orbs.len()],
+            score: 0,
+            track,
             current_lap_timer: Timer::new(),
             thruster_effect: None,
         }
     }
-    fn draw_impl(&mut self, framebuffer: &mut ugli::Framebuffer) {
-        ugli::clear(framebuffer, Some(Color::WHITE), None);
+    fn restart(&mut self) {
+        self.player = Player::new(self.track.spawn, self.track.spawn_rotation);
+        self.centerline = Centerline::from_track(&self.track);
+        self.progress = Progress::new(&self.centerline, self.player.pos);
+        self.racing_line = RacingLine::compute(&self.track, &self.centerline);
+        self.progress_history.clear();
+        self.wrong_way_timer = 0.0;
+        self.thrusters = [false; 2];
+        self.throttle = 0.0;
+        self.telemetry = Telemetry::new(&self.track);
+        self.lap_samples.clear();
+        self.lap_start = self.t;
+        self.lap_start_progress = self.progress.value;
+        self.ghost = None;
+        self.camera.target_position = self.player.pos;
+        self.camera.snap();
+        self.cinematic_spot = None;
+        self.particles.clear();
+        let (min, max) = self.track.bounds();
+        self.decals.reset(min, max);
+        self.laps_done = 0;
+        self.max_laps_done = 0;
+        self.current_lap_timer = Timer::new();
+        self.best_lap_time = None;
+        self.lap_penalties.clear();
+        self.last_lap = None;
+        self.touching_tires = false;
+        self.cutting = false;
+        self.boost_pad_contacts = vec![false; self.track.boost_pads.len()];
+        self.collected_orbs = vec![false; self.track.orbs.len()];
+        self.score = 0;
+        self.minimap.invalidate();
+    }
+    fn update_camera(&mut self, delta_time: f32) {
+        let look_ahead = self.player.pos + self.player.vel * 0.7;
+        let speed_fov = 20.0 + self.player.vel.len() * 0.3;
+        let spots = self.track.camera_spots();
+        let player_pos = self.player.pos;
+        let camera = &mut self.camera;
+        match self.settings.camera_mode {
+            CameraMode::Rotating => {
+                camera.target_position = look_ahead;
+                camera.target_fov = speed_fov;
+                camera.target_rotation = self.player.rotation - f32::PI / 2.0;
+            }
+            CameraMode::Overview => {
+                let (min, max) = self.track.bounds();
+                let size = max - min;
+                let window_size = self.geng.window().size().map(|x| x as f32);
+                camera.target_position = (min + max) / 2.0;
+                camera.target_fov = size.y.max(size.x * window_size.y / window_size.x);
+                camera.target_rotation = 0.0;
+            }
+            CameraMode::Cinematic if !spots.is_empty() => {
+                let dist = |index: usize| (spots[index] - player_pos).len();
+                let nearest = (0..spots.len())
+                    .min_by(|&a, &b| dist(a).partial_cmp(&dist(b)).unwrap())
+                    .unwrap();
+                let spot = match self.cinematic_spot {
+                    Some(current)
+                        if current < spots.len() && dist(current) < dist(nearest) * 1.2 =>
+                    {
+                        current
+                    }
+                    _ => nearest,
+                };
+                camera.target_position = spots[spot] * 0.4 + player_pos * 0.6;
+                camera.target_fov = (dist(spot) * 1.5).clamp(15.0, 60.0);
+                camera.target_rotation = 0.0;
+                if self.cinematic_spot != Some(spot) {
+                    self.cinematic_spot = Some(spot);
+                    camera.snap();
+                }
+            }
+            _ => {
+                camera.target_position = look_ahead;
+                camera.target_fov = speed_fov;
+                camera.target_rotation = 0.0;
+            }
+        }
+        camera.stiffness = self.settings.camera_stiffness;
+        camera.update(delta_time);
+    }
+    fn update_audio(&mut self) {
+        self.focused = window_focused();
+        let mixer = &self.settings.mixer;
+        if let Some(effect) = &mut self.thruster_effect {
+            effect.set_volume(THRUSTER_VOLUME * mixer.volume(Bus::Sfx, self.focused));
+        }
+        if let Some(effect) = &mut self.music_effect {
+            effect.set_volume(MUSIC_VOLUME * mixer.volume(Bus::Music, self.focused));
+        }
+    }
+    fn add_penalty(&mut self, reason: PenaltyReason) {
+        let rules = match &self.track.rules {
+            Some(rules) => rules,
+            None => return,
+        };
+        let penalty = match reason {
+            PenaltyReason::TireHit => rules.tire_hit,
+            PenaltyReason::Cut => rules.cut,
+        };
+        if let Some(penalty) = penalty {
+            self.lap_penalties.push((reason, penalty));
+        }
+    }
+    fn theme(&self) -> &Theme {
+        self.themes.get(
+            self.settings
+                .theme
+                .as_deref()
+                .or(self.track.theme.as_deref()),
+        )
+    }
+    fn draw_background(&self, batch: &mut Batch) {
         for &pos in &self.background {
-            self.renderer.draw(
-                framebuffer,
-                &self.camera,
-                pos,
-                3.0,
-                10.0,
-                Color::rgba(0.8, 0.8, 0.8, 0.6),
-            );
+            batch.circle(pos, 3.0, 10.0, self.theme().blobs);
         }
-        const N: usize = 10;
-        for i in 0..=N {
-            self.renderer.draw(
-                framebuffer,
-                &self.camera,
-                vec2(INNER + (OUTER - INNER) * i as f32 / N as f32, 0.0),
-                0.2,
-                0.4,
-                Color::rgba(0.5, 0.5, 0.5, 1.0),
-            );
+    }
+    fn draw_scene(&self, batch: &mut Batch) {
+        let theme = self.theme();
+        draw_track(batch, &self.track, theme, &self.collected_orbs, self.t);
+        if self.settings.racing_line {
+            self.racing_line.draw(batch, theme);
         }
-        for obstacle in &self.obstacles {
-            let inner_r = obstacle.r / 3.0;
-            self.renderer.draw(
-                framebuffer,
-                &self.camera,
-                obstacle.pos,
-                inner_r,
-                obstacle.r,
-                Color::GRAY,
-            );
-            self.renderer.draw(
+        if let Some(ghost) = &self.ghost {
+            ghost.draw(batch, theme, self.t);
+        }
+        draw_player(
+            batch,
+            &self.player,
+            &self.particles,
+            &self.settings.ship,
+            theme,
+            self.t,
+        );
+    }
+    fn draw_impl(&self, framebuffer: &mut ugli::Framebuffer, camera: &Camera) {
+        ugli::clear(framebuffer, Some(self.theme().background), None);
+        let mut batch = Batch::new();
+        self.draw_background(&mut batch);
+        self.renderer.draw_batch(framebuffer, camera, &batch);
+        self.decals.draw(framebuffer, camera);
+        let mut batch = Batch::new();
+        self.draw_scene(&mut batch);
+        if self.debug && self.editor.is_none() {
+            self.physics_debug.draw_world(&mut batch, &self.track);
+            draw_player_debug(&mut batch, &self.player);
+        }
+        self.renderer.draw_batch(framebuffer, camera, &batch);
+    }
+    fn draw_telemetry(&self, framebuffer: &mut ugli::Framebuffer, font_size: f32) {
+        let theme = self.theme();
+        let right = framebuffer.size().x as f32 - 5.0;
+        let mut y = font_size * 1.1 + 5.0;
+        let size = vec2(font_size * 4.0, font_size * 0.4);
+        let pos = vec2(right - size.x, y);
+        self.geng
+            .draw_2d()
+            .quad(framebuffer, AABB::pos_size(pos, size), theme.panel);
+        self.geng.draw_2d().quad(
+            framebuffer,
+            AABB::pos_size(pos, vec2(size.x * self.throttle, size.y)),
+            theme.thruster,
+        );
+        y += size.y + 5.0;
+        for (i, (name, &on)) in ["R", "L"]
+            .iter()
+            .zip(self.thrusters.iter().rev())
+            .enumerate()
+        {
+            self.font.draw_aligned(
                 framebuffer,
-                &self.camera,
-                obstacle.pos,
-                inner_r - 0.1,
-                inner_r + 0.1,
-                Color::BLACK,
+                name,
+                vec2(right - i as f32 * font_size, y),
+                1.0,
+                font_size * 0.7,
+                if on { theme.text } else { theme.hint },
             );
-            self.renderer.draw(
+        }
+        for text in &[
+            format!("SPIN: {:+.2}", self.player.w),
+            format!("SPEED: {:.1}", self.player.vel.len()),
+        ] {
+            y += font_size * 0.7;
+            self.font.draw_aligned(
                 framebuffer,
-                &self.camera,
-                obstacle.pos,
-                obstacle.r - 0.1,
-                obstacle.r + 0.1,
-                Color::BLACK,
+                text,
+                vec2(right, y),
+                1.0,
+                font_size * 0.7,
+                theme.text,
             );
         }
+    }
+    fn draw_options(&self, framebuffer: &mut ugli::Framebuffer) {
+        if let Some(options) = &self.options {
+            options.draw(&self.geng, framebuffer, &self.font, &self.settings);
+        }
+    }
+    fn active_camera(&self) -> &Camera {
+        match &self.editor {
+            Some(editor) => &editor.camera,
+            None => &self.camera,
+        }
+    }
+    fn screenshot(&self) {
+        let mut batch = Batch::new();
+        self.draw_background(&mut batch);
+        self.draw_scene(&mut batch);
+        let image = software::render(
+            self.geng.window().size(),
+            self.active_camera(),
+            self.theme().background,
+            self.settings.edge_softness(),
+            &batch,
+        );
+        if let Err(e) = image.save(self.data_dir.join("screenshot.png")) {
+            error!("Failed to save screenshot: {}", e);
+        }
+    }
+}
 
-        let head = self.player.head();
-        let left_thruster = self.player.left_thruster();
-        let right_thruster = self.player.right_thruster();
-
-        {
-            const N: usize = 10;
-            for i in 0..N / 2 {
-                let pos = left_thruster.pos
-                    + (right_thruster.pos - left_thruster.pos)
-                        * (i as f32 + (self.t * 10.0).fract())
-                        / N as f32;
-                self.renderer.draw(
-                    framebuffer,
-                    &self.camera,
-                    pos,
-                    0.0,
-                    0.1,
-                    Color::rgba(1.0, 0.0, 0.0, 0.5),
+fn draw_track(batch: &mut Batch, track: &Track, theme: &Theme, collected_orbs: &[bool], t: f32) {
+    let [start_a, start_b] = track.start_line;
+    const CHECKER: f32 = 0.5;
+    let n = ((start_b - start_a).len() / CHECKER).round().max(1.0) as usize;
+    let along = (start_b - start_a) / n as f32;
+    let across = vec2(-along.y, along.x).normalize() * CHECKER;
+    for i in 0..n {
+        for j in 0..2 {
+            let pos = start_a + along * i as f32 + across * (j as f32 - 1.0);
+            let color = theme.start_line[(i + j) % 2];
+            batch.polygon(
+                &[pos, pos + along, pos + along + across, pos + across],
+                color,
+            );
+        }
+    }
+    for hazard in &track.hazards {
+        match hazard.kind {
+            HazardKind::Oil { .. } => {
+                batch.circle(hazard.pos, 0.0, hazard.r, theme.oil);
+                for i in 0..5 {
+                    let pos = hazard.pos + Vec2::rotated(vec2(hazard.r * 0.5, 0.0), i as f32 * 1.3);
+                    batch.circle(pos, 0.0, hazard.r * 0.4, theme.oil);
+                }
+            }
+            HazardKind::Gravity { strength } => {
+                const N: usize = 4;
+                for i in 0..N {
+                    let mut t = (i as f32 + (t * 0.5).fract()) / N as f32;
+                    if strength > 0.0 {
+                        t = 1.0 - t;
+                    }
+                    let r = hazard.r * t;
+                    batch.circle(
+                        hazard.pos,
+                        r - 0.1,
+                        r + 0.1,
+                        Color {
+                            a: 1.0 - t,
+                            ..theme.gravity
+                        },
+                    );
+                }
+                batch.circle(
+                    hazard.pos,
+                    hazard.r - 0.1,
+                    hazard.r + 0.1,
+                    Color {
+                        a: 0.5,
+                        ..theme.gravity
+                    },
                 );
             }
-        }
-
-        {
-            const N: usize = 10;
-            for i in 0..N / 2 {
-                let pos = right_thruster.pos
-                    + (left_thruster.pos - right_thruster.pos)
-                        * (i as f32 + (self.t * 10.0).fract())
-                        / N as f32;
-                self.renderer.draw(
-                    framebuffer,
-                    &self.camera,
-                    pos,
-                    0.0,
-                    0.1,
-                    Color::rgba(1.0, 0.0, 0.0, 0.5),
+            HazardKind::Spin { w } => {
+                const N: usize = 3;
+                for i in 0..N {
+                    let angle = i as f32 * 2.0 * f32::PI / N as f32 + t * w;
+                    batch.arc(
+                        hazard.pos,
+                        hazard.r * 0.7,
+                        0.4,
+                        angle..angle + 1.2,
+                        theme.spin,
+                    );
+                }
+                batch.circle(
+                    hazard.pos,
+                    hazard.r - 0.1,
+                    hazard.r + 0.1,
+                    Color {
+                        a: 0.5,
+                        ..theme.spin
+                    },
                 );
             }
         }
-
-        self.renderer.draw(
-            framebuffer,
-            &self.camera,
-            head.pos,
-            0.0,
-            head.r,
-            Color::BLUE,
-        );
-        self.renderer.draw(
-            framebuffer,
-            &self.camera,
-            head.pos,
-            head.r - 0.1,
-            head.r + 0.1,
-            Color::BLACK,
-        );
-
-        for particle in &self.particles {
-            self.renderer.draw(
-                framebuffer,
-                &self.camera,
-                particle.pos,
+    }
+    for pad in &track.boost_pads {
+        batch.circle(pad.pos, 0.0, pad.r, theme.boost_pad_fill);
+        batch.circle(pad.pos, pad.r - 0.1, pad.r + 0.1, theme.boost_pad);
+        let dir = pad.impulse().normalize();
+        const N: usize = 3;
+        for i in 0..N {
+            let t = (i as f32 + (t * 2.0).fract()) / N as f32;
+            batch.circle(
+                pad.pos + dir * pad.r * (t * 1.6 - 0.8),
                 0.0,
-                particle.r,
-                particle.color,
+                0.25,
+                Color {
+                    a: 1.0 - t,
+                    ..theme.boost_pad
+                },
             );
         }
-
-        self.renderer.draw(
-            framebuffer,
-            &self.camera,
-            self.player.left_thruster_tube(),
-            0.0,
-            0.4,
-            Color::BLACK,
-        );
-        self.renderer.draw(
-            framebuffer,
-            &self.camera,
-            self.player.left_thruster_tube(),
-            0.0,
-            0.25,
-            Color::rgb(0.3, 0.3, 0.0),
+    }
+    for (orb, &collected) in track.orbs.iter().zip(collected_orbs) {
+        if collected {
+            continue;
+        }
+        let r = orb.r * (1.0 + (t * 4.0).sin() * 0.1);
+        batch.circle(orb.pos, 0.0, r, theme.orb);
+        batch.circle(orb.pos, r - 0.1, r + 0.1, theme.outline);
+    }
+    for obstacle in &track.tires {
+        let inner_r = obstacle.r / 3.0;
+        batch.circle(obstacle.pos, inner_r, obstacle.r, theme.tire);
+        batch.circle(obstacle.pos, inner_r - 0.1, inner_r + 0.1, theme.outline);
+        batch.circle(
+            obstacle.pos,
+            obstacle.r - 0.1,
+            obstacle.r + 0.1,
+            theme.outline,
         );
+    }
+}
 
-        self.renderer.draw(
-            framebuffer,
-            &self.camera,
-            self.player.right_thruster_tube(),
-            0.0,
-            0.4,
-            Color::BLACK,
+fn draw_player(
+    batch: &mut Batch,
+    player: &Player,
+    particles: &ParticleSystem,
+    style: &ShipStyle,
+    theme: &Theme,
+    t: f32,
+) {
+    let head = player.head();
+    let left_thruster = player.left_thruster();
+    let right_thruster = player.right_thruster();
+
+    batch.line(
+        left_thruster.pos,
+        right_thruster.pos,
+        0.15 + (t * 20.0).sin() * 0.05,
+        theme.beam,
+    );
+
+    batch.circle(head.pos, 0.0, head.r, style.head.color(theme.head));
+    batch.circle(head.pos, head.r - 0.1, head.r + 0.1, theme.outline);
+
+    particles.draw(batch);
+
+    batch.circle(player.left_thruster_tube(), 0.0, 0.4, theme.outline);
+    batch.circle(player.left_thruster_tube(), 0.0, 0.25, theme.thruster_tube);
+
+    batch.circle(player.right_thruster_tube(), 0.0, 0.4, theme.outline);
+    batch.circle(player.right_thruster_tube(), 0.0, 0.25, theme.thruster_tube);
+
+    let mut draw_thruster = |thruster: &Circle| {
+        batch.line(
+            head.pos,
+            thruster.pos,
+            0.15 + (t * 20.0).sin() * 0.05,
+            theme.beam,
         );
-        self.renderer.draw(
-            framebuffer,
-            &self.camera,
-            self.player.right_thruster_tube(),
+        batch.circle(
+            thruster.pos,
             0.0,
-            0.25,
-            Color::rgb(0.3, 0.3, 0.0),
+            thruster.r,
+            style.thrusters.color(theme.thruster),
         );
+        batch.circle(
+            thruster.pos,
+            thruster.r - 0.1,
+            thruster.r + 0.1,
+            theme.outline,
+        );
+    };
 
-        let mut draw_thruster = |thruster: &Circle| {
-            const N: usize = 10;
-            for i in 0..N {
-                let pos = head.pos
-                    + (thruster.pos - head.pos) * (i as f32 + (self.t * 10.0).fract()) / N as f32;
-                self.renderer.draw(
-                    framebuffer,
-                    &self.camera,
-                    pos,
-                    0.0,
-                    0.1,
-                    Color::rgba(1.0, 0.0, 0.0, 0.5),
-                );
-            }
-            self.renderer.draw(
-                framebuffer,
-                &self.camera,
-                thruster.pos,
-                0.0,
-                thruster.r,
-                Color::rgb(0.7, 0.7, 0.3),
-            );
-            self.renderer.draw(
-                framebuffer,
-                &self.camera,
-                thruster.pos,
-                thruster.r - 0.1,
-                thruster.r + 0.1,
-                Color::BLACK,
-            );
-        };
-
-        draw_thruster(&left_thruster);
-        draw_thruster(&right_thruster);
-    }
+    draw_thruster(&left_thruster);
+    draw_thruster(&right_thruster);
 }
 
 const FORCE: f32 = 10.0;
+const SKID_SPEED: f32 = 15.0;
 
 impl geng::State for Game {
     fn update(&mut self, delta_time: f64) {
         let delta_time = delta_time as f32;
         self.t += delta_time;
-        self.camera.target_position = self.player.pos + self.player.vel * 0.7;
-        self.camera.target_fov = 20.0 + self.player.vel.len() * 0.3;
-        self.camera.update(delta_time * 0.8);
+        self.update_audio();
+        self.physics_debug.begin_frame(delta_time);
+        if self.editor.is_some() {
+            return;
+        }
+        self.update_camera(delta_time);
+        let controls = self.options.is_none();
         let left_thruster = self.player.left_thruster();
         let mut left_thruster_force = vec2(0.0, 0.0);
-        if self.geng.window().is_key_pressed(geng::Key::Left) {
+        if controls && self.geng.window().is_key_pressed(geng::Key::Left) {
             left_thruster_force = Vec2::rotated(vec2(FORCE, 0.0), self.player.rotation);
         }
         self.player
             .apply_impulse(left_thruster_force * delta_time, left_thruster.pos);
+        self.physics_debug
+            .forces
+            .push((left_thruster.pos, left_thruster_force));
         let mut right_thruster_force = vec2(0.0, 0.0);
         let right_thruster = self.player.right_thruster();
-        if self.geng.window().is_key_pressed(geng::Key::Right) {
+        if controls && self.geng.window().is_key_pressed(geng::Key::Right) {
             right_thruster_force = Vec2::rotated(vec2(FORCE, 0.0), self.player.rotation);
         }
+        self.thrusters = [
+            left_thruster_force.len() > 0.1,
+            right_thruster_force.len() > 0.1,
+        ];
+        let throttle = self.thrusters.iter().filter(|&&on| on).count() as f32 / 2.0;
+        self.throttle +=
+            (throttle - self.throttle) * (1.0 - (-THROTTLE_RESPONSE * delta_time).exp());
         if left_thruster_force.len() + right_thruster_force.len() > 1.0 {
             if self.thruster_effect.is_none() {
                 let mut effect = self.assets.thruster.effect();
-                effect.set_volume(0.3);
+                effect.set_volume(
+                    THRUSTER_VOLUME * self.settings.mixer.volume(Bus::Sfx, self.focused),
+                );
                 effect.play();
                 self.thruster_effect = Some(effect);
             }
             if self.music_effect.is_none() {
                 let mut effect = self.assets.music.effect();
-                effect.set_volume(0.3);
+                effect.set_volume(
+                    MUSIC_VOLUME * self.settings.mixer.volume(Bus::Music, self.focused),
+                );
                 effect.play();
                 self.music_effect = Some(effect);
             }
@@ -437,104 +805,305 @@ impl geng::State for Game {
         }
         self.player
             .apply_impulse(right_thruster_force * delta_time, right_thruster.pos);
-        let last_arg = self.player.pos.arg();
-        self.player.update(delta_time);
-        let now_arg = self.player.pos.arg();
-        if now_arg.abs() < 1.0 {
-            if last_arg < 0.0 && now_arg >= 0.0 {
+        self.physics_debug
+            .forces
+            .push((right_thruster.pos, right_thruster_force));
+        let last_pos = self.player.pos;
+        let last_tubes = [
+            self.player.left_thruster_tube(),
+            self.player.right_thruster_tube(),
+        ];
+        let field = self.track.force_field(self.player.pos);
+        self.physics_debug
+            .forces
+            .push((self.player.pos, field.force));
+        self.player.update(delta_time, &field);
+        self.progress.update(&self.centerline, self.player.pos);
+        self.progress_history.record(self.t, self.progress.value);
+        if self.progress.wrong_way(&self.centerline, self.player.vel) {
+            self.wrong_way_timer += delta_time;
+        } else {
+            self.wrong_way_timer = 0.0;
+        }
+        if let Some(forward) = self.track.crosses_start_line(last_pos, self.player.pos) {
+            if forward {
                 self.laps_done += 1;
-                if self.best_lap_time.is_none()
-                    || self.best_lap_time.unwrap() > self.current_lap_timer.elapsed() as f32
-                {
-                    self.best_lap_time = Some(self.current_lap_timer.elapsed() as f32);
+                // Crossing back over a line that was just reversed over only
+                // recounts the lap; it doesn't finish a new one.
+                if self.laps_done > self.max_laps_done {
+                    self.max_laps_done = self.laps_done;
+                    let penalties = mem::take(&mut self.lap_penalties);
+                    let (penalty, valid) = total_penalty(&penalties);
+                    let time = self.current_lap_timer.elapsed() as f32 + penalty;
+                    let samples = mem::take(&mut self.lap_samples);
+                    if valid && (self.best_lap_time.is_none() || self.best_lap_time.unwrap() > time)
+                    {
+                        self.best_lap_time = Some(time);
+                        self.ghost = Some(Ghost::new(
+                            samples,
+                            self.settings.ship.clone(),
+                            &self.centerline,
+                        ));
+                    }
+                    self.lap_start = self.t;
+                    self.lap_start_progress = self.progress.value;
+                    self.last_lap = Some(LapResult {
+                        time,
+                        valid,
+                        penalties,
+                    });
+                    self.current_lap_timer = Timer::new();
+                    self.decals.lap();
+                    let [a, b] = self.track.start_line;
+                    let forward = vec2(a.y - b.y, b.x - a.x).arg();
+                    for i in 0..=4 {
+                        self.particles.burst(
+                            &self.confetti,
+                            a + (b - a) * (i as f32 / 4.0),
+                            vec2(0.0, 0.0),
+                            forward,
+                            30,
+                        );
+                    }
+                    for collected in &mut self.collected_orbs {
+                        *collected = false;
+                    }
                 }
-                self.current_lap_timer = Timer::new();
-            }
-            if last_arg >= 0.0 && now_arg < 0.0 {
+            } else {
+                // Penalties picked up since the line stay with the lap that is
+                // still in progress, so backing over it can't wipe them.
                 self.laps_done -= 1;
             }
         }
-        for obstacle in &self.obstacles {
+        let skid = self.theme().skid;
+        let speed = self.player.vel.len();
+        if speed > SKID_SPEED {
+            let strength = ((speed - SKID_SPEED) / SKID_SPEED).min(1.0);
+            let tubes = [
+                self.player.left_thruster_tube(),
+                self.player.right_thruster_tube(),
+            ];
+            for ((force, from), to) in [left_thruster_force, right_thruster_force]
+                .iter()
+                .zip(last_tubes)
+                .zip(tubes)
+            {
+                if force.len() > 0.1 {
+                    self.decals.batch.line(
+                        from,
+                        to,
+                        0.25,
+                        Color {
+                            a: skid.a * strength,
+                            ..skid
+                        },
+                    );
+                }
+            }
+        }
+        let mut touching_tires = false;
+        let mut hit = false;
+        for obstacle in &self.track.tires {
             if let Some(collision) = self.player.collide(obstacle) {
                 self.player.pos -= collision.normal * collision.penetration;
                 let impulse = -collision.normal * Vec2::dot(collision.normal, self.player.vel);
                 let volume = (impulse.len() * 0.3).min(1.0);
                 if volume > 0.1 {
                     let mut effect = self.assets.bump.effect();
-                    effect.set_volume(volume as f64 * 0.3);
+                    effect.set_volume(
+                        volume as f64
+                            * BUMP_VOLUME
+                            * self.settings.mixer.volume(Bus::Sfx, self.focused),
+                    );
                     effect.play();
+                    if self.settings.screen_shake {
+                        self.camera.add_trauma(volume * 0.6);
+                    }
+                    self.particles.burst(
+                        &self.sparks,
+                        collision.pos,
+                        self.player.vel,
+                        (-collision.normal).arg(),
+                        (volume * 20.0) as usize,
+                    );
+                }
+                touching_tires = true;
+                self.decals.batch.circle(collision.pos, 0.0, 0.3, skid);
+                if let Some(rules) = &self.track.rules {
+                    if impulse.len() >= rules.min_hit_impulse {
+                        hit = true;
+                    }
                 }
                 self.player.apply_impulse(impulse, collision.pos);
+                self.physics_debug.impulses.push((collision.pos, impulse));
+                self.physics_debug.contacts.push(collision);
             }
         }
-        self.next_thruster_particle -= delta_time;
-        while self.next_thruster_particle < 0.0 {
-            self.next_thruster_particle += 1.0 / 100.0;
-            if left_thruster_force.len() > 0.1 {
-                self.particles.push(Particle {
-                    pos: self.player.left_thruster_tube(),
-                    vel: self.player.vel * 0.5 - left_thruster_force * 0.1
-                        + vec2(
-                            global_rng().gen_range(-1.0..=1.0),
-                            global_rng().gen_range(-1.0..=1.0),
-                        ) * 0.6,
-                    r: 0.2,
-                    color: Color::rgba(1.0, 0.5, 0.0, 0.5),
-                    life: 1.0,
-                });
+        if !touching_tires {
+            self.touching_tires = false;
+        } else if hit && !self.touching_tires {
+            self.touching_tires = true;
+            self.add_penalty(PenaltyReason::TireHit);
+        }
+        let cutting = self.track.is_cutting(self.player.pos);
+        if cutting && !self.cutting {
+            self.add_penalty(PenaltyReason::Cut);
+        }
+        self.cutting = cutting;
+        for (pad, contact) in self
+            .track
+            .boost_pads
+            .iter()
+            .zip(&mut self.boost_pad_contacts)
+        {
+            let touching = self.player.collide(&pad.circle()).is_some();
+            if touching && !*contact {
+                self.player.apply_impulse(pad.impulse(), self.player.pos);
+                self.physics_debug
+                    .impulses
+                    .push((self.player.pos, pad.impulse()));
+            }
+            *contact = touching;
+        }
+        for (orb, collected) in self.track.orbs.iter().zip(&mut self.collected_orbs) {
+            if !*collected && self.player.collide(&orb.circle()).is_some() {
+                *collected = true;
+                self.score += orb.score;
             }
-            if right_thruster_force.len() > 0.1 {
-                self.particles.push(Particle {
-                    pos: self.player.right_thruster_tube(),
-                    vel: self.player.vel * 0.5 - right_thruster_force * 0.1
-                        + vec2(
-                            global_rng().gen_range(-1.0..=1.0),
-                            global_rng().gen_range(-1.0..=1.0),
-                        ) * 0.6,
-                    r: 0.2,
-                    color: Color::rgba(1.0, 0.5, 0.0, 0.5),
-                    life: 1.0,
-                });
+        }
+        let exhaust = self
+            .exhaust
+            .get(self.settings.ship.trail, self.theme().exhaust);
+        let count = self.exhaust_timer.tick(delta_time, exhaust.rate);
+        for (force, pos) in [
+            (left_thruster_force, self.player.left_thruster_tube()),
+            (right_thruster_force, self.player.right_thruster_tube()),
+        ] {
+            if force.len() > 0.1 {
+                self.particles
+                    .burst(&exhaust, pos, self.player.vel, (-force).arg(), count);
             }
         }
-        for particle in &mut self.particles {
-            particle.update(delta_time);
+        let (penalty, valid) = total_penalty(&self.lap_penalties);
+        let sample = Sample {
+            time: self.t,
+            lap: self.laps_done,
+            penalty,
+            valid,
+            pos: self.player.pos,
+            vel: self.player.vel,
+            rotation: self.player.rotation,
+            w: self.player.w,
+            left_thruster: self.thrusters[0],
+            right_thruster: self.thrusters[1],
+            collisions: self.physics_debug.contacts.len(),
+        };
+        self.lap_samples.push(Sample {
+            time: self.t - self.lap_start,
+            ..sample.clone()
+        });
+        self.telemetry.record(sample);
+        let exhaust_color = self.theme().exhaust;
+        if let Some(ghost) = &mut self.ghost {
+            ghost.update(self.t - self.lap_start, delta_time, exhaust_color);
         }
-        self.particles.retain(|particle| particle.life > 0.0);
+        self.particles.update(delta_time);
     }
-    fn draw(&mut self, framebuffer: &mut ugli::Framebuffer) {
-        if true {
-            let texture_height = 200;
-            let mut texture = ugli::Texture2d::new_uninitialized(
-                self.geng.ugli(),
-                vec2(
-                    texture_height * framebuffer.size().x / framebuffer.size().y,
-                    texture_height,
-                ),
-            );
-            {
-                let mut framebuffer = ugli::Framebuffer::new_color(
-                    self.geng.ugli(),
-                    ugli::ColorAttachment::Texture(&mut texture),
-                );
-                self.draw_impl(&mut framebuffer);
+    fn handle_event(&mut self, event: geng::Event) {
+        if let geng::Event::KeyDown {
+            key: geng::Key::Escape,
+        } = event
+        {
+            if self.options.take().is_none() {
+                self.options = Some(OptionsMenu::new());
             }
-            texture.set_filter(ugli::Filter::Nearest);
-            let framebuffer_size = framebuffer.size();
-            ugli::clear(framebuffer, Some(Color::WHITE), None);
-            self.geng.draw_2d().textured_quad(
-                framebuffer,
-                AABB::pos_size(
-                    vec2(0.0, framebuffer_size.y as f32),
-                    vec2(framebuffer_size.x as f32, -(framebuffer_size.y as f32)),
-                ),
-                &texture,
-                Color::WHITE,
+            return;
+        }
+        if let Some(options) = &mut self.options {
+            options.handle_event(&mut self.settings, &self.themes, &event);
+            return;
+        }
+        match event {
+            geng::Event::KeyDown {
+                key: geng::Key::F12,
+            } => {
+                self.screenshot();
+                return;
+            }
+            geng::Event::KeyDown { key: geng::Key::F3 } => {
+                self.debug = !self.debug;
+                return;
+            }
+            geng::Event::KeyDown { key: geng::Key::F5 } => {
+                if let Err(e) = self.telemetry.export(&self.data_dir.join("telemetry")) {
+                    error!("Failed to export telemetry: {}", e);
+                }
+                return;
+            }
+            geng::Event::KeyDown { key: geng::Key::M } => {
+                self.settings.mixer.music_enabled = !self.settings.mixer.music_enabled;
+                return;
+            }
+            geng::Event::KeyDown { key: geng::Key::F2 } => {
+                self.settings.smooth_edges = !self.settings.smooth_edges;
+                return;
+            }
+            geng::Event::KeyDown { key: geng::Key::C } => {
+                self.settings.camera_mode = cycle(&CameraMode::ALL, self.settings.camera_mode, 1);
+                return;
+            }
+            geng::Event::KeyDown { key: geng::Key::E } => {
+                if self.editor.take().is_some() {
+                    self.restart();
+                } else {
+                    if let Some(mut effect) = self.thruster_effect.take() {
+                        effect.pause();
+                    }
+                    self.editor = Some(Editor::new(&self.camera));
+                }
+                return;
+            }
+            _ => {}
+        }
+        if let Some(editor) = &mut self.editor {
+            editor.handle_event(
+                &mut self.track,
+                &event,
+                self.geng.window().size().map(|x| x as f32),
             );
+        }
+    }
+    fn draw(&mut self, framebuffer: &mut ugli::Framebuffer) {
+        self.decals.flush(&self.renderer);
+        self.renderer
+            .set_edge_softness(self.settings.edge_softness());
+        let camera = self.active_camera();
+        let offscreen = self.settings.pixelated || !self.settings.post_passes().is_empty();
+        let viewport = if offscreen && self.editor.is_none() {
+            let speed = self.player.vel.len();
+            let velocity = Vec2::rotated(self.player.vel, -camera.rotation)
+                * (SPEED_BLUR_TIME / camera.fov)
+                * ((speed - SPEED_BLUR_MIN) / speed.max(1e-3)).max(0.0);
+            self.offscreen.draw(
+                &self.geng,
+                framebuffer,
+                &self.settings,
+                velocity,
+                |framebuffer| self.draw_impl(framebuffer, camera),
+            )
         } else {
-            self.draw_impl(framebuffer);
+            self.draw_impl(framebuffer, camera);
+            Viewport::full(framebuffer.size().map(|x| x as f32))
+        };
+
+        if let Some(editor) = &self.editor {
+            editor.draw(&self.renderer, framebuffer, &self.track);
+            editor.draw_hud(framebuffer, &self.font, self.theme());
+            self.draw_options(framebuffer);
+            return;
         }
 
+        let theme = self.theme();
         let framebuffer_size = framebuffer.size();
         let font_size = (framebuffer.size().y / 20) as f32;
 
@@ -544,7 +1113,7 @@ impl geng::State for Game {
                 vec2(0.0, 0.0),
                 vec2(framebuffer_size.x as f32, font_size * 1.1),
             ),
-            Color::rgba(1.0, 1.0, 1.0, 0.5),
+            theme.panel,
         );
 
         self.geng.draw_2d().quad(
@@ -553,52 +1122,42 @@ impl geng::State for Game {
                 vec2(0.0, framebuffer_size.y as f32 - font_size * 1.1),
                 vec2(framebuffer_size.x as f32, font_size * 1.1),
             ),
-            Color::rgba(1.0, 1.0, 1.0, 0.5),
+            theme.panel,
         );
 
         self.font.draw_aligned(
             framebuffer,
             "LEFT for",
-            self.camera.world_to_screen(
-                framebuffer_size.map(|x| x as f32),
-                vec2((INNER + OUTER) / 2.0, 3.0),
-            ) + vec2(0.0, font_size * 0.7),
+            viewport.world_to_screen(&self.camera, self.track.spawn + vec2(0.0, 3.0))
+                + vec2(0.0, font_size * 0.7),
             0.5,
             font_size * 0.7,
-            Color::rgba(0.5, 0.5, 0.5, 1.0),
+            theme.hint,
         );
         self.font.draw_aligned(
             framebuffer,
             "left thruster",
-            self.camera.world_to_screen(
-                framebuffer_size.map(|x| x as f32),
-                vec2((INNER + OUTER) / 2.0, 3.0),
-            ),
+            viewport.world_to_screen(&self.camera, self.track.spawn + vec2(0.0, 3.0)),
             0.5,
             font_size * 0.7,
-            Color::rgba(0.5, 0.5, 0.5, 1.0),
+            theme.hint,
         );
         self.font.draw_aligned(
             framebuffer,
             "RIGHT for",
-            self.camera.world_to_screen(
-                framebuffer_size.map(|x| x as f32),
-                vec2((INNER + OUTER) / 2.0, -3.0),
-            ),
+            viewport.world_to_screen(&self.camera, self.track.spawn + vec2(0.0, -3.0)),
             0.5,
             font_size * 0.7,
-            Color::rgba(0.5, 0.5, 0.5, 1.0),
+            theme.hint,
         );
         self.font.draw_aligned(
             framebuffer,
             "right thruster",
-            self.camera.world_to_screen(
-                framebuffer_size.map(|x| x as f32),
-                vec2((INNER + OUTER) / 2.0, -3.0),
-            ) + vec2(0.0, -font_size * 0.7),
+            viewport.world_to_screen(&self.camera, self.track.spawn + vec2(0.0, -3.0))
+                + vec2(0.0, -font_size * 0.7),
             0.5,
             font_size * 0.7,
-            Color::rgba(0.5, 0.5, 0.5, 1.0),
+            theme.hint,
         );
 
         self.font.draw(
@@ -610,16 +1169,27 @@ impl geng::State for Game {
             ),
             vec2(5.0, 5.0),
             font_size,
-            Color::BLACK,
+            theme.text,
         );
 
+        if !self.track.orbs.is_empty() {
+            self.font.draw_aligned(
+                framebuffer,
+                &format!("SCORE: {}", self.score),
+                vec2(framebuffer_size.x as f32 / 2.0, 5.0),
+                0.5,
+                font_size,
+                theme.text,
+            );
+        }
+
         self.font.draw_aligned(
             framebuffer,
             &format!("LAPS DONE: {}", self.laps_done),
             vec2(framebuffer_size.x as f32 - 5.0, 5.0),
             1.0,
             font_size,
-            Color::BLACK,
+            theme.text,
         );
 
         self.font.draw(
@@ -631,7 +1201,7 @@ impl geng::State for Game {
             ),
             vec2(5.0, framebuffer_size.y as f32 - font_size - 5.0),
             font_size,
-            Color::BLACK,
+            theme.text,
         );
 
         match self.best_lap_time {
@@ -644,7 +1214,7 @@ impl geng::State for Game {
                 ),
                 1.0,
                 font_size,
-                Color::BLACK,
+                theme.text,
             ),
             None => self.font.draw_aligned(
                 framebuffer,
@@ -655,13 +1225,123 @@ impl geng::State for Game {
                 ),
                 1.0,
                 font_size,
-                Color::BLACK,
+                theme.text,
             ),
         }
+
+        if let Some(lap) = &self.last_lap {
+            let mut pos = vec2(
+                framebuffer_size.x as f32 - 5.0,
+                framebuffer_size.y as f32 - font_size * 2.0 - 5.0,
+            );
+            let text = if lap.valid {
+                format!(
+                    "LAST LAP: {}:{}",
+                    (lap.time as i32) / 60,
+                    (lap.time as i32) % 60
+                )
+            } else {
+                "LAST LAP: INVALID".to_owned()
+            };
+            self.font
+                .draw_aligned(framebuffer, &text, pos, 1.0, font_size * 0.7, theme.text);
+            let mut listed: Vec<(PenaltyReason, Penalty, usize)> = Vec::new();
+            for &(reason, penalty) in &lap.penalties {
+                match listed
+                    .iter_mut()
+                    .find(|(r, p, _)| *r == reason && *p == penalty)
+                {
+                    Some((_, _, count)) => *count += 1,
+                    None => listed.push((reason, penalty, 1)),
+                }
+            }
+            for (reason, penalty, count) in listed {
+                pos.y -= font_size * 0.7;
+                self.font.draw_aligned(
+                    framebuffer,
+                    &format!("{} x{} {}", reason.name(), count, penalty_text(penalty)),
+                    pos,
+                    1.0,
+                    font_size * 0.7,
+                    theme.hint,
+                );
+            }
+        }
+
+        self.draw_telemetry(framebuffer, font_size);
+
+        let lap_time = self.t - self.lap_start;
+        if let Some((ghost, ghost_progress)) = self.ghost.as_ref().and_then(|ghost| {
+            ghost
+                .progress(lap_time)
+                .map(|progress| (ghost, self.lap_start_progress + progress))
+        }) {
+            let order = race_order(&[self.progress.value, ghost_progress]);
+            let position = order.iter().position(|&i| i == 0).unwrap() + 1;
+            let gap = if position == 1 {
+                -self.progress_history.gap(ghost_progress, self.t)
+            } else {
+                ghost.gap(self.progress.value - self.lap_start_progress, lap_time)
+            };
+            self.font.draw_aligned(
+                framebuffer,
+                &format!("POS {}/{} GAP {:+.2}", position, order.len(), gap),
+                vec2(
+                    framebuffer_size.x as f32 / 2.0,
+                    framebuffer_size.y as f32 - font_size - 5.0,
+                ),
+                0.5,
+                font_size,
+                theme.text,
+            );
+        }
+        if self.wrong_way_timer > WRONG_WAY_DELAY {
+            self.font.draw_aligned(
+                framebuffer,
+                "WRONG WAY",
+                framebuffer_size.map(|x| x as f32) / 2.0 + vec2(0.0, font_size * 3.0),
+                0.5,
+                font_size * 2.0,
+                theme.beam,
+            );
+        }
+
+        if self.debug {
+            self.physics_debug.draw_graph(
+                &self.geng,
+                framebuffer,
+                &self.font,
+                theme,
+                vec2(5.0, framebuffer_size.y as f32 - font_size * 4.5),
+                vec2(font_size * 6.0, font_size * 2.0),
+            );
+        }
+
+        if self.settings.minimap {
+            let mut markers = vec![Marker {
+                pos: self.player.pos,
+                rotation: self.player.rotation,
+                color: self.settings.ship.head.color(theme.head),
+            }];
+            markers.extend(self.ghost.as_ref().and_then(|ghost| ghost.marker(theme)));
+            self.minimap.draw(
+                framebuffer,
+                vec2(5.0, font_size * 1.1 + 5.0),
+                &self.track,
+                theme,
+                &markers,
+            );
+        }
+
+        self.draw_options(framebuffer);
     }
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // Settings and exports are written next to where the game was launched
+    // from, not into the shipped static/ assets.
+    let cwd = std::env::current_dir().unwrap_or_default();
     if let Some(dir) = std::env::var_os("CARGO_MANIFEST_DIR") {
         std::env::set_current_dir(std::path::Path::new(&dir).join("static")).unwrap();
     } else {
@@ -672,6 +1352,13 @@ fn main() {
             }
         }
     }
+    if args.first().map(|arg| arg.as_str()) == Some("compare") {
+        if let Err(e) = compare::run(&args[1..], &cwd) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
     let geng = Rc::new(Geng::new(geng::ContextOptions {
         title: "TriJam 135".to_owned(),
         ..default()
@@ -683,7 +1370,7 @@ fn main() {
             &geng,
             geng::EmptyLoadingScreen,
             geng::LoadAsset::load(&geng, "."),
-            move |assets| Game::new(&geng_clone, assets.unwrap()),
+            move |assets| Game::new(&geng_clone, assets.unwrap(), cwd),
         ),
     );
 }
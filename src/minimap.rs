@@ -0,0 +1,100 @@
+use super::*;
+
+const TEXTURE_SIZE: usize = 256;
+const SCREEN_SIZE: f32 = 0.25;
+const MARKER_SIZE: f32 = 0.03;
+
+pub struct Marker {
+    pub pos: Vec2<f32>,
+    pub rotation: f32,
+    pub color: Color<f32>,
+}
+
+pub struct Minimap {
+    geng: Rc<Geng>,
+    renderer: Rc<Renderer>,
+    cache: RefCell<Option<(String, ugli::Texture)>>,
+}
+
+impl Minimap {
+    pub fn new(geng: &Rc<Geng>, renderer: &Rc<Renderer>) -> Self {
+        Self {
+            geng: geng.clone(),
+            renderer: renderer.clone(),
+            cache: RefCell::new(None),
+        }
+    }
+    pub fn invalidate(&self) {
+        *self.cache.borrow_mut() = None;
+    }
+    fn render(&self, track: &Track, theme: &Theme) -> ugli::Texture {
+        let size = vec2(TEXTURE_SIZE, TEXTURE_SIZE);
+        let mut texture = ugli::Texture::new_uninitialized(self.geng.ugli(), size);
+        let mut framebuffer = ugli::Framebuffer::new_color(
+            self.geng.ugli(),
+            ugli::ColorAttachment::Texture(&mut texture),
+        );
+        ugli::clear(
+            &mut framebuffer,
+            Some(Color::rgba(0.0, 0.0, 0.0, 0.0)),
+            None,
+        );
+        let (min, max) = track.bounds();
+        let size = size.map(|x| x as f32);
+        let camera = Camera::fit(min, max, &Viewport::full(size), size);
+        let extent = (max - min).x.max((max - min).y);
+        let mut batch = Batch::new();
+        for tire in &track.tires {
+            batch.circle(tire.pos, 0.0, tire.r, theme.tire);
+        }
+        for pad in &track.boost_pads {
+            batch.circle(pad.pos, 0.0, pad.r, theme.boost_pad);
+        }
+        let [a, b] = track.start_line;
+        batch.line(a, b, extent * 0.015, theme.text);
+        self.renderer.draw_batch(&mut framebuffer, &camera, &batch);
+        texture
+    }
+    pub fn draw(
+        &self,
+        framebuffer: &mut ugli::Framebuffer,
+        pos: Vec2<f32>,
+        track: &Track,
+        theme: &Theme,
+        markers: &[Marker],
+    ) {
+        let mut cache = self.cache.borrow_mut();
+        if cache.as_ref().map(|(name, _)| name != &theme.name) != Some(false) {
+            *cache = Some((theme.name.clone(), self.render(track, theme)));
+        }
+        let (_, texture) = cache.as_ref().unwrap();
+        let framebuffer_size = framebuffer.size().map(|x| x as f32);
+        let size = framebuffer_size.y * SCREEN_SIZE;
+        let viewport = Viewport {
+            pos,
+            size: vec2(size, size),
+        };
+        self.geng
+            .draw_2d()
+            .quad(framebuffer, AABB::pos_size(pos, viewport.size), theme.panel);
+        self.geng.draw_2d().textured_quad(
+            framebuffer,
+            AABB::pos_size(pos + vec2(0.0, size), vec2(size, -size)),
+            texture,
+            Color::WHITE,
+        );
+        let (min, max) = track.bounds();
+        let camera = Camera::fit(min, max, &viewport, framebuffer_size);
+        let r = (max - min).x.max((max - min).y) * MARKER_SIZE;
+        let mut batch = Batch::new();
+        for marker in markers {
+            let point =
+                |x: f32, y: f32| marker.pos + Vec2::rotated(vec2(x, y) * r, marker.rotation);
+            batch.polygon(
+                &[point(1.0, 0.0), point(-0.7, 0.7), point(-0.7, -0.7)],
+                marker.color,
+            );
+        }
+        self.renderer.draw_batch(framebuffer, &camera, &batch);
+    }
+}
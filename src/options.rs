@@ -0,0 +1,233 @@
+use super::*;
+
+struct Item {
+    name: &'static str,
+    value: fn(&Settings) -> String,
+    change: fn(&mut Settings, &Themes, i32),
+}
+
+fn on_off(value: bool) -> String {
+    if value { "ON" } else { "OFF" }.to_owned()
+}
+
+fn volume(value: f64) -> String {
+    format!("{:.0}%", value * 100.0)
+}
+
+const ITEMS: &[Item] = &[
+    Item {
+        name: "SMOOTH EDGES",
+        value: |settings| on_off(settings.smooth_edges),
+        change: |settings, _, _| settings.smooth_edges = !settings.smooth_edges,
+    },
+    Item {
+        name: "PIXELATED",
+        value: |settings| on_off(settings.pixelated),
+        change: |settings, _, _| settings.pixelated = !settings.pixelated,
+    },
+    Item {
+        name: "RESOLUTION",
+        value: |settings| format!("{}P", settings.pixel_height),
+        change: |settings, _, delta| {
+            settings.pixel_height = cycle(&PIXEL_HEIGHTS, settings.pixel_height, delta)
+        },
+    },
+    Item {
+        name: "FILTER",
+        value: |settings| settings.pixel_filter.name().to_owned(),
+        change: |settings, _, delta| {
+            settings.pixel_filter = cycle(&PixelFilter::ALL, settings.pixel_filter, delta)
+        },
+    },
+    Item {
+        name: "SCALING",
+        value: |settings| settings.scaling.name().to_owned(),
+        change: |settings, _, delta| {
+            settings.scaling = cycle(&Scaling::ALL, settings.scaling, delta)
+        },
+    },
+    Item {
+        name: "PALETTE",
+        value: |settings| on_off(settings.palette),
+        change: |settings, _, _| settings.palette = !settings.palette,
+    },
+    Item {
+        name: "SPEED BLUR",
+        value: |settings| on_off(settings.speed_blur),
+        change: |settings, _, _| settings.speed_blur = !settings.speed_blur,
+    },
+    Item {
+        name: "VIGNETTE",
+        value: |settings| on_off(settings.vignette),
+        change: |settings, _, _| settings.vignette = !settings.vignette,
+    },
+    Item {
+        name: "CRT",
+        value: |settings| on_off(settings.crt),
+        change: |settings, _, _| settings.crt = !settings.crt,
+    },
+    Item {
+        name: "THEME",
+        value: |settings| match &settings.theme {
+            Some(theme) => theme.to_uppercase(),
+            None => "TRACK DEFAULT".to_owned(),
+        },
+        change: |settings, themes, delta| {
+            let options: Vec<Option<&str>> = std::iter::once(None)
+                .chain(themes.names().map(Some))
+                .collect();
+            settings.theme = cycle(&options, settings.theme.as_deref(), delta).map(str::to_owned);
+        },
+    },
+    Item {
+        name: "SHIP HEAD",
+        value: |settings| settings.ship.head.name().to_owned(),
+        change: |settings, _, delta| {
+            settings.ship.head = cycle(&ShipColor::ALL, settings.ship.head, delta)
+        },
+    },
+    Item {
+        name: "SHIP THRUSTERS",
+        value: |settings| settings.ship.thrusters.name().to_owned(),
+        change: |settings, _, delta| {
+            settings.ship.thrusters = cycle(&ShipColor::ALL, settings.ship.thrusters, delta)
+        },
+    },
+    Item {
+        name: "TRAIL",
+        value: |settings| settings.ship.trail.name().to_owned(),
+        change: |settings, _, delta| {
+            settings.ship.trail = cycle(&TrailStyle::ALL, settings.ship.trail, delta)
+        },
+    },
+    Item {
+        name: "CAMERA",
+        value: |settings| settings.camera_mode.name().to_owned(),
+        change: |settings, _, delta| {
+            settings.camera_mode = cycle(&CameraMode::ALL, settings.camera_mode, delta)
+        },
+    },
+    Item {
+        name: "SCREEN SHAKE",
+        value: |settings| on_off(settings.screen_shake),
+        change: |settings, _, _| settings.screen_shake = !settings.screen_shake,
+    },
+    Item {
+        name: "CAMERA STIFFNESS",
+        value: |settings| format!("{:.1}", settings.camera_stiffness),
+        change: |settings, _, delta| {
+            settings.camera_stiffness = cycle(&CAMERA_STIFFNESSES, settings.camera_stiffness, delta)
+        },
+    },
+    Item {
+        name: "MINIMAP",
+        value: |settings| on_off(settings.minimap),
+        change: |settings, _, _| settings.minimap = !settings.minimap,
+    },
+    Item {
+        name: "RACING LINE",
+        value: |settings| on_off(settings.racing_line),
+        change: |settings, _, _| settings.racing_line = !settings.racing_line,
+    },
+    Item {
+        name: "MASTER VOLUME",
+        value: |settings| volume(settings.mixer.master),
+        change: |settings, _, delta| {
+            settings.mixer.master = cycle(&VOLUMES, settings.mixer.master, delta)
+        },
+    },
+    Item {
+        name: "MUSIC VOLUME",
+        value: |settings| volume(settings.mixer.music),
+        change: |settings, _, delta| {
+            settings.mixer.music = cycle(&VOLUMES, settings.mixer.music, delta)
+        },
+    },
+    Item {
+        name: "SFX VOLUME",
+        value: |settings| volume(settings.mixer.sfx),
+        change: |settings, _, delta| {
+            settings.mixer.sfx = cycle(&VOLUMES, settings.mixer.sfx, delta)
+        },
+    },
+    Item {
+        name: "MUSIC",
+        value: |settings| on_off(settings.mixer.music_enabled),
+        change: |settings, _, _| settings.mixer.music_enabled = !settings.mixer.music_enabled,
+    },
+    // Focus is only known in the browser; natively the window never reports losing it.
+    #[cfg(target_arch = "wasm32")]
+    Item {
+        name: "MUTE UNFOCUSED",
+        value: |settings| on_off(settings.mixer.mute_unfocused),
+        change: |settings, _, _| settings.mixer.mute_unfocused = !settings.mixer.mute_unfocused,
+    },
+];
+
+pub struct OptionsMenu {
+    selected: usize,
+}
+
+impl OptionsMenu {
+    pub fn new() -> Self {
+        Self { selected: 0 }
+    }
+    pub fn handle_event(&mut self, settings: &mut Settings, themes: &Themes, event: &geng::Event) {
+        if let geng::Event::KeyDown { key } = *event {
+            match key {
+                geng::Key::Up => self.selected = (self.selected + ITEMS.len() - 1) % ITEMS.len(),
+                geng::Key::Down => self.selected = (self.selected + 1) % ITEMS.len(),
+                geng::Key::Left => (ITEMS[self.selected].change)(settings, themes, -1),
+                geng::Key::Right | geng::Key::Enter => {
+                    (ITEMS[self.selected].change)(settings, themes, 1)
+                }
+                _ => {}
+            }
+        }
+    }
+    pub fn draw(
+        &self,
+        geng: &Geng,
+        framebuffer: &mut ugli::Framebuffer,
+        font: &geng::Font,
+        settings: &Settings,
+    ) {
+        let framebuffer_size = framebuffer.size().map(|x| x as f32);
+        let font_size = framebuffer_size.y / 20.0;
+        let spacing = font_size * (16.0 / ITEMS.len() as f32).min(0.9);
+        geng.draw_2d().quad(
+            framebuffer,
+            AABB::pos_size(vec2(0.0, 0.0), framebuffer_size),
+            Color::rgba(1.0, 1.0, 1.0, 0.8),
+        );
+        let mut pos = vec2(
+            framebuffer_size.x / 2.0,
+            framebuffer_size.y / 2.0 + spacing * ITEMS.len() as f32 / 2.0,
+        );
+        font.draw_aligned(framebuffer, "OPTIONS", pos, 0.5, font_size, Color::BLACK);
+        for (index, item) in ITEMS.iter().enumerate() {
+            pos.y -= spacing;
+            let color = if index == self.selected {
+                Color::rgb(1.0, 0.0, 0.0)
+            } else {
+                Color::BLACK
+            };
+            font.draw_aligned(
+                framebuffer,
+                &format!("{}: {}", item.name, (item.value)(settings)),
+                pos,
+                0.5,
+                (font_size * 0.7).min(spacing * 0.85),
+                color,
+            );
+        }
+        font.draw_aligned(
+            framebuffer,
+            "UP/DOWN SELECT  LEFT/RIGHT CHANGE  ESC CLOSE",
+            vec2(framebuffer_size.x / 2.0, font_size),
+            0.5,
+            font_size * 0.6,
+            Color::rgba(0.3, 0.3, 0.3, 1.0),
+        );
+    }
+}
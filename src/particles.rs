@@ -0,0 +1,207 @@
+use super::*;
+
+use std::collections::VecDeque;
+
+pub const MAX_PARTICLES: usize = 2000;
+
+pub struct Emitter {
+    pub rate: f32,
+    pub lifetime: f32,
+    pub speed: f32,
+    pub spread: f32,
+    pub jitter: f32,
+    pub inherit_velocity: f32,
+    pub colors: Vec<Color<f32>>,
+    pub tints: Vec<Color<f32>>,
+    pub size: [f32; 2],
+    pub gravity: Vec2<f32>,
+    pub drag: f32,
+}
+
+impl Emitter {
+    pub fn exhaust(colors: Vec<Color<f32>>, size: [f32; 2]) -> Self {
+        Self {
+            rate: 100.0,
+            lifetime: 1.0,
+            speed: 1.0,
+            spread: 0.0,
+            jitter: 0.6,
+            inherit_velocity: 0.5,
+            colors,
+            tints: Vec::new(),
+            size,
+            gravity: vec2(0.0, 0.0),
+            drag: 0.0,
+        }
+    }
+    pub fn sparks() -> Self {
+        Self {
+            rate: 0.0,
+            lifetime: 0.4,
+            speed: 8.0,
+            spread: 0.8,
+            jitter: 1.0,
+            inherit_velocity: 0.2,
+            colors: vec![
+                Color::rgba(1.0, 1.0, 0.6, 1.0),
+                Color::rgba(1.0, 0.5, 0.0, 0.8),
+                Color::rgba(0.8, 0.1, 0.0, 0.0),
+            ],
+            tints: Vec::new(),
+            size: [0.12, 0.04],
+            gravity: vec2(0.0, 0.0),
+            drag: 3.0,
+        }
+    }
+    pub fn confetti() -> Self {
+        Self {
+            rate: 0.0,
+            lifetime: 2.5,
+            speed: 10.0,
+            spread: 1.2,
+            jitter: 2.0,
+            inherit_velocity: 0.0,
+            colors: vec![Color::WHITE, Color::WHITE, Color::rgba(1.0, 1.0, 1.0, 0.0)],
+            tints: vec![
+                Color::rgb(1.0, 0.2, 0.2),
+                Color::rgb(1.0, 0.8, 0.0),
+                Color::rgb(0.2, 0.8, 0.2),
+                Color::rgb(0.2, 0.5, 1.0),
+                Color::rgb(0.8, 0.3, 1.0),
+            ],
+            size: [0.25, 0.25],
+            gravity: vec2(0.0, -6.0),
+            drag: 1.5,
+        }
+    }
+    fn color(&self, t: f32) -> Color<f32> {
+        let last = self.colors.len() - 1;
+        let x = t.clamp(0.0, 1.0) * last as f32;
+        let i = (x as usize).min(last.saturating_sub(1));
+        let a = self.colors[i];
+        let b = self.colors[(i + 1).min(last)];
+        let t = x - i as f32;
+        Color::rgba(
+            a.r + (b.r - a.r) * t,
+            a.g + (b.g - a.g) * t,
+            a.b + (b.b - a.b) * t,
+            a.a + (b.a - a.a) * t,
+        )
+    }
+}
+
+pub struct Particle {
+    pub pos: Vec2<f32>,
+    pub vel: Vec2<f32>,
+    pub age: f32,
+    pub tint: Color<f32>,
+    pub emitter: Rc<Emitter>,
+}
+
+impl Particle {
+    pub fn update(&mut self, delta_time: f32) {
+        self.vel += self.emitter.gravity * delta_time;
+        self.vel -= self.vel * (self.emitter.drag * delta_time).min(1.0);
+        self.pos += self.vel * delta_time;
+        self.age += delta_time;
+    }
+    fn progress(&self) -> f32 {
+        self.age / self.emitter.lifetime
+    }
+    pub fn color(&self) -> Color<f32> {
+        let color = self.emitter.color(self.progress());
+        Color::rgba(
+            color.r * self.tint.r,
+            color.g * self.tint.g,
+            color.b * self.tint.b,
+            color.a * self.tint.a,
+        )
+    }
+    pub fn size(&self) -> f32 {
+        let [start, end] = self.emitter.size;
+        start + (end - start) * self.progress().min(1.0)
+    }
+}
+
+#[derive(Default)]
+pub struct EmitTimer {
+    next: f32,
+}
+
+impl EmitTimer {
+    pub fn tick(&mut self, delta_time: f32, rate: f32) -> usize {
+        if rate <= 0.0 {
+            return 0;
+        }
+        self.next -= delta_time;
+        let mut count = 0;
+        while self.next < 0.0 {
+            self.next += 1.0 / rate;
+            count += 1;
+        }
+        count
+    }
+}
+
+pub struct ParticleSystem {
+    particles: VecDeque<Particle>,
+    max_particles: usize,
+}
+
+impl ParticleSystem {
+    pub fn new(max_particles: usize) -> Self {
+        Self {
+            particles: VecDeque::new(),
+            max_particles,
+        }
+    }
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+    pub fn spawn(&mut self, emitter: &Rc<Emitter>, pos: Vec2<f32>, vel: Vec2<f32>, direction: f32) {
+        if self.particles.len() >= self.max_particles {
+            self.particles.pop_front();
+        }
+        let mut rng = global_rng();
+        let angle = direction + emitter.spread * rng.gen_range(-1.0..=1.0);
+        let jitter = vec2(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0)) * emitter.jitter;
+        let tint = if emitter.tints.is_empty() {
+            Color::WHITE
+        } else {
+            emitter.tints[rng.gen_range(0..emitter.tints.len())]
+        };
+        self.particles.push_back(Particle {
+            pos,
+            vel: vel * emitter.inherit_velocity
+                + Vec2::rotated(vec2(emitter.speed, 0.0), angle)
+                + jitter,
+            age: 0.0,
+            tint,
+            emitter: emitter.clone(),
+        });
+    }
+    pub fn burst(
+        &mut self,
+        emitter: &Rc<Emitter>,
+        pos: Vec2<f32>,
+        vel: Vec2<f32>,
+        direction: f32,
+        count: usize,
+    ) {
+        for _ in 0..count {
+            self.spawn(emitter, pos, vel, direction);
+        }
+    }
+    pub fn update(&mut self, delta_time: f32) {
+        for particle in &mut self.particles {
+            particle.update(delta_time);
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.emitter.lifetime);
+    }
+    pub fn draw(&self, batch: &mut Batch) {
+        for particle in &self.particles {
+            batch.circle(particle.pos, 0.0, particle.size(), particle.color());
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use super::*;
+
+use std::collections::VecDeque;
+
+const WALK_STEP: f32 = 1.0;
+const MAX_WALK_STEPS: usize = 10000;
+const MAX_HALF_WIDTH: f32 = 30.0;
+const RECENTER: f32 = 0.5;
+const WRONG_WAY_SPEED: f32 = 3.0;
+const HISTORY_SIZE: usize = 3600;
+
+fn wall_distance(track: &Track, pos: Vec2<f32>, dir: Vec2<f32>) -> Option<f32> {
+    track
+        .tires
+        .iter()
+        .filter_map(|tire| {
+            let delta = tire.pos - pos;
+            let along = Vec2::dot(delta, dir);
+            let across = Vec2::skew(dir, delta);
+            if along <= 0.0 || across.abs() >= tire.r {
+                return None;
+            }
+            Some((along - (tire.r * tire.r - across * across).sqrt()).max(0.0))
+        })
+        .filter(|&distance| distance < MAX_HALF_WIDTH)
+        .min_by(|a, b| a.partial_cmp(b).unwrap())
+}
+
+// Follows the corridor between the walls from the start line until it comes
+// back around, recentering between the nearest tires on either side.
+fn walk_track(track: &Track, start: Vec2<f32>, forward: Vec2<f32>) -> Vec<Vec2<f32>> {
+    let mut points = vec![start];
+    let mut pos = start;
+    let mut dir = forward;
+    for _ in 0..MAX_WALK_STEPS {
+        let mut next = pos + dir * WALK_STEP;
+        let normal = vec2(-dir.y, dir.x);
+        if let (Some(left), Some(right)) = (
+            wall_distance(track, next, normal),
+            wall_distance(track, next, -normal),
+        ) {
+            next += normal * (left - right) * RECENTER / 2.0;
+        }
+        if points.len() > 2 && track.crosses_start_line(pos, next) == Some(true) {
+            return points;
+        }
+        dir = (next - pos).normalize();
+        pos = next;
+        points.push(pos);
+    }
+    Vec::new()
+}
+
+pub struct Centerline {
+    points: Vec<Vec2<f32>>,
+    distances: Vec<f32>,
+    length: f32,
+}
+
+impl Centerline {
+    pub fn new(points: Vec<Vec2<f32>>) -> Self {
+        let mut distances = Vec::with_capacity(points.len());
+        let mut length = 0.0;
+        for (i, &point) in points.iter().enumerate() {
+            distances.push(length);
+            length += (points[(i + 1) % points.len()] - point).len();
+        }
+        Self {
+            points,
+            distances,
+            length,
+        }
+    }
+    pub fn from_track(track: &Track) -> Self {
+        let [a, b] = track.start_line;
+        let start = (a + b) / 2.0;
+        let forward = vec2(a.y - b.y, b.x - a.x);
+        if track.centerline.len() >= 2 {
+            let mut centerline = Self::new(track.centerline.clone());
+            if Vec2::dot(centerline.direction(centerline.project(start)), forward) < 0.0 {
+                centerline.points.reverse();
+                centerline = Self::new(centerline.points);
+            }
+            return centerline;
+        }
+        Self::new(walk_track(track, start, forward.normalize()))
+    }
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+    fn segment_at(&self, distance: f32) -> (usize, f32) {
+        let distance = distance.rem_euclid(self.length);
+        let i = self
+            .distances
+            .iter()
+            .rposition(|&d| d <= distance)
+            .unwrap_or(0);
+        (i, distance - self.distances[i])
+    }
+    pub fn point(&self, distance: f32) -> Vec2<f32> {
+        let (i, offset) = self.segment_at(distance);
+        let a = self.points[i];
+        let b = self.points[(i + 1) % self.points.len()];
+        a + (b - a) * (offset / (b - a).len().max(1e-6))
+    }
+    pub fn project(&self, pos: Vec2<f32>) -> f32 {
+        let mut best = (f32::INFINITY, 0.0);
+        for (i, &a) in self.points.iter().enumerate() {
+            let b = self.points[(i + 1) % self.points.len()];
+            let segment = b - a;
+            let t = (Vec2::dot(pos - a, segment) / Vec2::dot(segment, segment).max(1e-6))
+                .clamp(0.0, 1.0);
+            let dist = (a + segment * t - pos).len();
+            if dist < best.0 {
+                best = (dist, self.distances[i] + segment.len() * t);
+            }
+        }
+        best.1
+    }
+    pub fn direction(&self, distance: f32) -> Vec2<f32> {
+        let (i, _) = self.segment_at(distance);
+        (self.points[(i + 1) % self.points.len()] - self.points[i]).normalize()
+    }
+}
+
+pub struct Progress {
+    distance: f32,
+    pub value: f32,
+}
+
+impl Progress {
+    pub fn new(centerline: &Centerline, pos: Vec2<f32>) -> Self {
+        let distance = centerline.project(pos);
+        Self {
+            distance,
+            value: if centerline.length() > 0.0 {
+                distance / centerline.length()
+            } else {
+                0.0
+            },
+        }
+    }
+    pub fn update(&mut self, centerline: &Centerline, pos: Vec2<f32>) {
+        let length = centerline.length();
+        if length <= 0.0 {
+            return;
+        }
+        let distance = centerline.project(pos);
+        let delta = (distance - self.distance + length / 2.0).rem_euclid(length) - length / 2.0;
+        self.distance = distance;
+        self.value += delta / length;
+    }
+    pub fn wrong_way(&self, centerline: &Centerline, vel: Vec2<f32>) -> bool {
+        centerline.length() > 0.0
+            && Vec2::dot(vel, centerline.direction(self.distance)) < -WRONG_WAY_SPEED
+    }
+}
+
+pub fn race_order(progress: &[f32]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..progress.len()).collect();
+    order.sort_by(|&a, &b| progress[b].partial_cmp(&progress[a]).unwrap());
+    order
+}
+
+#[derive(Default)]
+pub struct ProgressHistory {
+    samples: VecDeque<(f32, f32)>,
+}
+
+impl ProgressHistory {
+    pub fn record(&mut self, time: f32, progress: f32) {
+        if let Some(&(_, last)) = self.samples.back() {
+            if progress <= last {
+                return;
+            }
+        }
+        if self.samples.len() == HISTORY_SIZE {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((time, progress));
+    }
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+    pub fn gap(&self, progress: f32, time: f32) -> f32 {
+        let i = self.samples.partition_point(|&(_, p)| p < progress);
+        self.samples.get(i).map_or(0.0, |&(t, _)| time - t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> Centerline {
+        Centerline::new(vec![
+            vec2(0.0, 0.0),
+            vec2(10.0, 0.0),
+            vec2(10.0, 10.0),
+            vec2(0.0, 10.0),
+        ])
+    }
+
+    #[test]
+    fn progress_is_continuous_across_start() {
+        let centerline = square();
+        let mut progress = Progress::new(&centerline, vec2(5.0, 1.0));
+        for &pos in &[
+            vec2(9.0, 5.0),
+            vec2(5.0, 9.0),
+            vec2(1.0, 5.0),
+            vec2(1.0, 1.0),
+            vec2(5.0, 1.0),
+        ] {
+            progress.update(&centerline, pos);
+        }
+        assert!((progress.value - 1.125).abs() < 1e-4);
+        progress.update(&centerline, vec2(1.0, 1.0));
+        assert!((progress.value - 1.025).abs() < 1e-4);
+    }
+
+    fn shifted_track(offset: Vec2<f32>) -> Track {
+        let mut track = Track::parse(include_str!("../static/track.json"));
+        track.spawn += offset;
+        for pos in &mut track.start_line {
+            *pos += offset;
+        }
+        for tire in &mut track.tires {
+            tire.pos += offset;
+        }
+        track
+    }
+
+    #[test]
+    fn derives_centerline_from_walls() {
+        let centerline = Centerline::from_track(&shifted_track(vec2(0.0, 0.0)));
+        assert!((centerline.length() - 2.0 * f32::PI * 62.5).abs() < 30.0);
+        for &point in &centerline.points {
+            assert!((55.0..70.0).contains(&point.len()), "{:?}", point);
+        }
+        let offset = vec2(300.0, -150.0);
+        let shifted = Centerline::from_track(&shifted_track(offset));
+        assert!((shifted.length() - centerline.length()).abs() < 1.0);
+        let pos = vec2(0.0, 62.5);
+        let progress = Progress::new(&centerline, pos).value;
+        let shifted_progress = Progress::new(&shifted, pos + offset).value;
+        assert!((progress - shifted_progress).abs() < 0.01);
+    }
+
+    #[test]
+    fn empty_track_has_no_progress() {
+        let mut track = shifted_track(vec2(0.0, 0.0));
+        track.tires.clear();
+        let centerline = Centerline::from_track(&track);
+        let mut progress = Progress::new(&centerline, vec2(1.0, 2.0));
+        progress.update(&centerline, vec2(3.0, 4.0));
+        assert_eq!(progress.value, 0.0);
+        assert!(!progress.wrong_way(&centerline, vec2(-10.0, 0.0)));
+    }
+
+    #[test]
+    fn orders_by_progress() {
+        assert_eq!(race_order(&[0.5, 1.2, 0.9]), vec![1, 2, 0]);
+        let mut history = ProgressHistory::default();
+        for &(time, progress) in &[(0.0, 0.0), (1.0, 0.5), (1.5, 0.4), (2.0, 1.0)] {
+            history.record(time, progress);
+        }
+        assert!((history.gap(0.5, 3.0) - 2.0).abs() < 1e-4);
+        assert!((history.gap(0.7, 3.0) - 1.0).abs() < 1e-4);
+        assert_eq!(history.gap(1.5, 3.0), 0.0);
+    }
+}
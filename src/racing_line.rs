@@ -0,0 +1,254 @@
+use super::*;
+
+const SPACING: f32 = 2.0;
+const ITERATIONS: usize = 1000;
+const SMOOTHING_PASSES: usize = 50;
+const MIN_STEP: f64 = 1e-4;
+const SHIP_MARGIN: f32 = 1.8;
+const MAX_WIDTH: f32 = 10.0;
+const LATERAL_ACCELERATION: f32 = FORCE;
+const ACCELERATION: f32 = 2.0 * FORCE;
+const BRAKING: f32 = 2.0 * FORCE;
+const DRAG: f32 = 0.9;
+
+pub struct RacingLine {
+    pub points: Vec<Vec2<f32>>,
+    pub braking: Vec<bool>,
+}
+
+fn curvature(a: Vec2<f32>, b: Vec2<f32>, c: Vec2<f32>) -> f32 {
+    let area = Vec2::skew(b - a, c - b).abs();
+    2.0 * area / ((b - a).len() * (c - b).len() * (c - a).len()).max(1e-6)
+}
+
+fn bending(a: Vec2<f64>, b: Vec2<f64>, c: Vec2<f64>) -> f64 {
+    let (ab, bc) = ((b - a).len(), (c - b).len());
+    let k = 2.0 * Vec2::skew(b - a, c - b).abs() / (ab * bc * (c - a).len()).max(1e-12);
+    k * k * (ab + bc) / 2.0
+}
+
+// Minimizes the discrete bending energy, the sum of squared curvature times
+// length, by projected gradient descent on the offsets. The gradient is
+// smoothed along the track first, coarse to fine; moving single points alone
+// can't make progress against the stiff local curvature terms.
+fn minimize_curvature(
+    centers: &[Vec2<f32>],
+    normals: &[Vec2<f32>],
+    limits: &[(f32, f32)],
+) -> Vec<f32> {
+    let n = centers.len();
+    let centers: Vec<Vec2<f64>> = centers.iter().map(|p| p.map(|x| x as f64)).collect();
+    let normals: Vec<Vec2<f64>> = normals.iter().map(|p| p.map(|x| x as f64)).collect();
+    let clamp = |i: usize, offset: f64| offset.max(limits[i].0 as f64).min(limits[i].1 as f64);
+    let point = |offsets: &[f64], i: usize| centers[i % n] + normals[i % n] * offsets[i % n];
+    let bend = |offsets: &[f64], i: usize| {
+        bending(
+            point(offsets, i + n - 1),
+            point(offsets, i),
+            point(offsets, i + 1),
+        )
+    };
+    let energy = |offsets: &[f64]| (0..n).map(|i| bend(offsets, i)).sum::<f64>();
+
+    let mut offsets: Vec<f64> = (0..n).map(|i| clamp(i, 0.0)).collect();
+    let mut current = energy(&offsets);
+    let mut step = 1.0;
+    let mut passes = SMOOTHING_PASSES;
+    for _ in 0..ITERATIONS {
+        let mut gradient = vec![0.0; n];
+        let mut probe = offsets.clone();
+        for i in 0..n {
+            const EPSILON: f64 = 1e-4;
+            let mut local = |offset: f64| {
+                probe[i] = offset;
+                bend(&probe, i + n - 1) + bend(&probe, i) + bend(&probe, i + 1)
+            };
+            gradient[i] =
+                (local(offsets[i] + EPSILON) - local(offsets[i] - EPSILON)) / (2.0 * EPSILON);
+            probe[i] = offsets[i];
+        }
+        for _ in 0..passes {
+            gradient = (0..n)
+                .map(|i| {
+                    (gradient[(i + n - 1) % n] + 2.0 * gradient[i] + gradient[(i + 1) % n]) / 4.0
+                })
+                .collect();
+        }
+        let max = gradient.iter().fold(0.0f64, |max, g| max.max(g.abs()));
+        if max < 1e-12 {
+            break;
+        }
+        while step >= MIN_STEP {
+            let candidate: Vec<f64> = (0..n)
+                .map(|i| clamp(i, offsets[i] - gradient[i] / max * step))
+                .collect();
+            let value = energy(&candidate);
+            if value < current {
+                offsets = candidate;
+                current = value;
+                step *= 1.5;
+                break;
+            }
+            step /= 2.0;
+        }
+        if step < MIN_STEP {
+            // Once the smoothed direction stops helping, refine with less
+            // smoothing so the local wiggles get straightened out too.
+            if passes == 0 {
+                break;
+            }
+            passes /= 2;
+            step = 1.0;
+        }
+    }
+    offsets.into_iter().map(|offset| offset as f32).collect()
+}
+
+impl RacingLine {
+    pub fn compute(track: &Track, centerline: &Centerline) -> Self {
+        if centerline.length() <= 0.0 {
+            return Self {
+                points: Vec::new(),
+                braking: Vec::new(),
+            };
+        }
+        let n = ((centerline.length() / SPACING) as usize).max(3);
+        let step = centerline.length() / n as f32;
+        let centers: Vec<Vec2<f32>> = (0..n).map(|i| centerline.point(i as f32 * step)).collect();
+        let normals: Vec<Vec2<f32>> = (0..n)
+            .map(|i| {
+                let dir = (centers[(i + 1) % n] - centers[(i + n - 1) % n]).normalize();
+                vec2(-dir.y, dir.x)
+            })
+            .collect();
+        let limits: Vec<(f32, f32)> = (0..n)
+            .map(|i| {
+                let dir = vec2(normals[i].y, -normals[i].x);
+                let mut limits = (-MAX_WIDTH, MAX_WIDTH);
+                for tire in &track.tires {
+                    let delta = tire.pos - centers[i];
+                    if Vec2::dot(delta, dir).abs() > tire.r + SPACING {
+                        continue;
+                    }
+                    let offset = Vec2::dot(delta, normals[i]);
+                    if offset > 0.0 {
+                        limits.1 = limits.1.min(offset - tire.r - SHIP_MARGIN);
+                    } else {
+                        limits.0 = limits.0.max(offset + tire.r + SHIP_MARGIN);
+                    }
+                }
+                if limits.0 > limits.1 {
+                    let middle = (limits.0 + limits.1) / 2.0;
+                    limits = (middle, middle);
+                }
+                limits
+            })
+            .collect();
+        let offsets = minimize_curvature(&centers, &normals, &limits);
+        let points: Vec<Vec2<f32>> = (0..n)
+            .map(|i| centers[i] + normals[i] * offsets[i])
+            .collect();
+
+        let top_speed = ACCELERATION / DRAG;
+        let mut speeds: Vec<f32> = (0..n)
+            .map(|i| {
+                let k = curvature(points[(i + n - 1) % n], points[i], points[(i + 1) % n]);
+                (LATERAL_ACCELERATION / k.max(1e-6)).sqrt().min(top_speed)
+            })
+            .collect();
+        for _ in 0..2 {
+            for i in 0..n {
+                let prev = (i + n - 1) % n;
+                let ds = (points[i] - points[prev]).len();
+                let acceleration = (ACCELERATION - DRAG * speeds[prev]).max(0.0);
+                let reachable = (speeds[prev].powi(2) + 2.0 * acceleration * ds).sqrt();
+                speeds[i] = speeds[i].min(reachable);
+            }
+        }
+        let accelerated = speeds.clone();
+        for _ in 0..2 {
+            for i in (0..n).rev() {
+                let next = (i + 1) % n;
+                let ds = (points[next] - points[i]).len();
+                let reachable = (speeds[next].powi(2) + 2.0 * BRAKING * ds).sqrt();
+                speeds[i] = speeds[i].min(reachable);
+            }
+        }
+        let braking = (0..n).map(|i| speeds[i] + 0.5 < accelerated[i]).collect();
+        Self { points, braking }
+    }
+    pub fn draw(&self, batch: &mut Batch, theme: &Theme) {
+        let n = self.points.len();
+        for i in 0..n {
+            let color = if self.braking[i] {
+                theme.beam
+            } else {
+                Color {
+                    a: 0.6,
+                    ..theme.hint
+                }
+            };
+            batch.line(self.points[i], self.points[(i + 1) % n], 0.3, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(r: f32) -> Vec<Vec2<f32>> {
+        let n = (2.0 * f32::PI * r / 2.0) as usize;
+        (0..n)
+            .map(|i| Vec2::rotated(vec2(r, 0.0), i as f32 * 2.0 * f32::PI / n as f32))
+            .collect()
+    }
+
+    #[test]
+    fn follows_outside_of_ring() {
+        let tires: Vec<Circle> = ring(20.0)
+            .into_iter()
+            .chain(ring(40.0))
+            .map(|pos| Circle { pos, r: 1.0 })
+            .collect();
+        let track = Track::parse(
+            &serde_json::json!({
+                "spawn": vec2(30.0, 0.0),
+                "spawn_rotation": f32::PI / 2.0,
+                "start_line": [vec2(20.0, 0.0), vec2(40.0, 0.0)],
+                "tires": tires,
+            })
+            .to_string(),
+        );
+        let centerline = Centerline::new(ring(30.0));
+        let line = RacingLine::compute(&track, &centerline);
+        for &point in &line.points {
+            for tire in &track.tires {
+                assert!((point - tire.pos).len() >= tire.r + SHIP_MARGIN - 1e-3);
+            }
+        }
+        let radius =
+            line.points.iter().map(|point| point.len()).sum::<f32>() / line.points.len() as f32;
+        assert!(radius > 35.0, "average radius {}", radius);
+    }
+
+    fn total_bending(points: &[Vec2<f32>]) -> f64 {
+        let n = points.len();
+        let point = |i: usize| points[i % n].map(|x| x as f64);
+        (0..n)
+            .map(|i| bending(point(i + n - 1), point(i), point(i + 1)))
+            .sum()
+    }
+
+    #[test]
+    fn bends_less_than_centerline() {
+        let track = Track::parse(include_str!("../static/track.json"));
+        let centerline = Centerline::from_track(&track);
+        let line = RacingLine::compute(&track, &centerline);
+        let n = line.points.len();
+        let step = centerline.length() / n as f32;
+        let centers: Vec<Vec2<f32>> = (0..n).map(|i| centerline.point(i as f32 * step)).collect();
+        let (a, b) = (total_bending(&line.points), total_bending(&centers));
+        assert!(a < b * 0.5, "{} vs {}", a, b);
+    }
+}
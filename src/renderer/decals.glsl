@@ -0,0 +1,24 @@
+varying vec2 v_uv;
+
+#ifdef VERTEX_SHADER
+attribute vec2 a_pos;
+
+uniform mat4 u_projection_matrix;
+uniform mat4 u_view_matrix;
+uniform vec2 u_bounds_pos;
+uniform vec2 u_bounds_size;
+
+void main() {
+    v_uv = a_pos;
+    vec2 pos = u_bounds_pos + a_pos * u_bounds_size;
+    gl_Position = u_projection_matrix * u_view_matrix * vec4(pos, 0.0, 1.0);
+}
+#endif
+
+#ifdef FRAGMENT_SHADER
+uniform sampler2D u_texture;
+
+void main() {
+    gl_FragColor = texture2D(u_texture, v_uv);
+}
+#endif
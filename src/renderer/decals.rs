@@ -0,0 +1,184 @@
+use super::*;
+
+const PIXELS_PER_UNIT: f32 = 8.0;
+const MAX_TEXTURE_SIZE: f32 = 2048.0;
+const LAP_FADE: f32 = 0.125;
+
+struct Layer {
+    pos: Vec2<f32>,
+    size: Vec2<f32>,
+    texture: ugli::Texture,
+    back: ugli::Texture,
+    marks: ugli::Texture,
+}
+
+pub struct Decals {
+    geng: Rc<Geng>,
+    quad: ugli::VertexBuffer<Vertex>,
+    program: ugli::Program,
+    fade_program: ugli::Program,
+    stamp_program: ugli::Program,
+    layer: Option<Layer>,
+    pub batch: Batch,
+    pending_fades: usize,
+}
+
+fn new_texture(geng: &Geng, size: Vec2<usize>) -> ugli::Texture {
+    let mut texture = ugli::Texture::new_uninitialized(geng.ugli(), size);
+    ugli::clear(
+        &mut ugli::Framebuffer::new_color(
+            geng.ugli(),
+            ugli::ColorAttachment::Texture(&mut texture),
+        ),
+        Some(Color::rgba(0.0, 0.0, 0.0, 0.0)),
+        None,
+    );
+    texture
+}
+
+impl Decals {
+    pub fn new(geng: &Rc<Geng>) -> Self {
+        Self {
+            geng: geng.clone(),
+            quad: ugli::VertexBuffer::new_static(
+                geng.ugli(),
+                vec![
+                    Vertex {
+                        a_pos: vec2(0.0, 0.0),
+                    },
+                    Vertex {
+                        a_pos: vec2(1.0, 0.0),
+                    },
+                    Vertex {
+                        a_pos: vec2(1.0, 1.0),
+                    },
+                    Vertex {
+                        a_pos: vec2(0.0, 1.0),
+                    },
+                ],
+            ),
+            program: geng
+                .shader_lib()
+                .compile(include_str!("decals.glsl"))
+                .unwrap(),
+            fade_program: geng
+                .shader_lib()
+                .compile(&format!(
+                    "{}\n{}",
+                    include_str!("postprocess/quad.glsl"),
+                    include_str!("fade.glsl")
+                ))
+                .unwrap(),
+            stamp_program: geng
+                .shader_lib()
+                .compile(&format!(
+                    "{}\n{}",
+                    include_str!("postprocess/quad.glsl"),
+                    include_str!("stamp.glsl")
+                ))
+                .unwrap(),
+            layer: None,
+            batch: Batch::new(),
+            pending_fades: 0,
+        }
+    }
+    pub fn reset(&mut self, min: Vec2<f32>, max: Vec2<f32>) {
+        let size = max - min;
+        let scale = PIXELS_PER_UNIT.min(MAX_TEXTURE_SIZE / size.x.max(size.y));
+        let texture_size = size.map(|x| ((x * scale).ceil() as usize).max(1));
+        self.layer = Some(Layer {
+            pos: min,
+            size,
+            texture: new_texture(&self.geng, texture_size),
+            back: new_texture(&self.geng, texture_size),
+            marks: new_texture(&self.geng, texture_size),
+        });
+        self.batch = Batch::new();
+        self.pending_fades = 0;
+    }
+    pub fn lap(&mut self) {
+        self.pending_fades += 1;
+    }
+    pub fn flush(&mut self, renderer: &Renderer) {
+        let layer = match &mut self.layer {
+            Some(layer) => layer,
+            None => return,
+        };
+        if self.pending_fades > 0 {
+            let size = layer.texture.size().map(|x| x as f32);
+            ugli::draw(
+                &mut ugli::Framebuffer::new_color(
+                    self.geng.ugli(),
+                    ugli::ColorAttachment::Texture(&mut layer.back),
+                ),
+                &self.fade_program,
+                ugli::DrawMode::TriangleFan,
+                &self.quad,
+                ugli::uniforms! {
+                    u_viewport_pos: vec2(0.0, 0.0),
+                    u_viewport_size: size,
+                    u_framebuffer_size: size,
+                    u_texture: &layer.texture,
+                    u_amount: LAP_FADE * self.pending_fades as f32,
+                },
+                ugli::DrawParameters { ..default() },
+            );
+            mem::swap(&mut layer.texture, &mut layer.back);
+            self.pending_fades = 0;
+        }
+        if !self.batch.runs.is_empty() {
+            let mut camera = Camera::new(layer.size.y);
+            camera.center = layer.pos + layer.size / 2.0;
+            let mut marks = ugli::Framebuffer::new_color(
+                self.geng.ugli(),
+                ugli::ColorAttachment::Texture(&mut layer.marks),
+            );
+            ugli::clear(&mut marks, Some(Color::rgba(0.0, 0.0, 0.0, 0.0)), None);
+            renderer.draw_batch_with(&mut marks, &camera, &self.batch, None);
+            let size = layer.texture.size().map(|x| x as f32);
+            ugli::draw(
+                &mut ugli::Framebuffer::new_color(
+                    self.geng.ugli(),
+                    ugli::ColorAttachment::Texture(&mut layer.back),
+                ),
+                &self.stamp_program,
+                ugli::DrawMode::TriangleFan,
+                &self.quad,
+                ugli::uniforms! {
+                    u_viewport_pos: vec2(0.0, 0.0),
+                    u_viewport_size: size,
+                    u_framebuffer_size: size,
+                    u_texture: &layer.texture,
+                    u_marks: &layer.marks,
+                },
+                ugli::DrawParameters { ..default() },
+            );
+            mem::swap(&mut layer.texture, &mut layer.back);
+            self.batch = Batch::new();
+        }
+    }
+    pub fn draw(&self, framebuffer: &mut ugli::Framebuffer, camera: &Camera) {
+        let layer = match &self.layer {
+            Some(layer) => layer,
+            None => return,
+        };
+        ugli::draw(
+            framebuffer,
+            &self.program,
+            ugli::DrawMode::TriangleFan,
+            &self.quad,
+            (
+                camera.uniforms(framebuffer.size().map(|x| x as f32)),
+                ugli::uniforms! {
+                    u_bounds_pos: layer.pos,
+                    u_bounds_size: layer.size,
+                    u_texture: &layer.texture,
+                },
+            ),
+            ugli::DrawParameters {
+                blend_mode: Some(default()),
+                ..default()
+            },
+        );
+    }
+}
@@ -0,0 +1,9 @@
+#ifdef FRAGMENT_SHADER
+uniform sampler2D u_texture;
+uniform float u_amount;
+
+void main() {
+    vec4 color = texture2D(u_texture, v_uv);
+    gl_FragColor = vec4(color.rgb, max(color.a - u_amount, 0.0));
+}
+#endif
@@ -1,67 +1,295 @@
 use super::*;
 
+mod decals;
+mod offscreen;
+mod postprocess;
+pub mod software;
+
+pub use decals::*;
+pub use offscreen::*;
+pub use postprocess::*;
+
 #[derive(ugli::Vertex, Clone)]
 pub struct Vertex {
     pub a_pos: Vec2<f32>,
 }
 
+#[derive(Clone)]
+pub struct Disc {
+    pub pos: Vec2<f32>,
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    pub color: Color<f32>,
+}
+
+#[derive(Clone)]
+pub struct TriangleVertex {
+    pub pos: Vec2<f32>,
+    pub color: Color<f32>,
+}
+
+const NO_EDGE: f32 = 1000.0;
+
+#[derive(ugli::Vertex, Clone)]
+struct BatchVertex {
+    a_pos: Vec2<f32>,
+    a_vt: Vec2<f32>,
+    a_inner: f32,
+    a_radius: f32,
+    a_edges: Vec3<f32>,
+    a_color: Color<f32>,
+}
+
+pub fn edge_distance(a: Vec2<f32>, b: Vec2<f32>, pos: Vec2<f32>) -> f32 {
+    let len = (b - a).len();
+    if len < 1e-6 {
+        return NO_EDGE;
+    }
+    Vec2::skew(b - a, pos - a).abs() / len
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Primitive {
+    Circles,
+    Triangles,
+}
+
+pub struct Run {
+    pub primitive: Primitive,
+    pub range: std::ops::Range<usize>,
+}
+
+#[derive(Default)]
+pub struct Batch {
+    pub discs: Vec<Disc>,
+    pub triangles: Vec<TriangleVertex>,
+    pub triangle_edges: Vec<[bool; 3]>,
+    pub runs: Vec<Run>,
+}
+
+impl Batch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    fn extend_run(&mut self, primitive: Primitive, end: usize) {
+        match self.runs.last_mut() {
+            Some(run) if run.primitive == primitive => run.range.end = end,
+            _ => {
+                let start = match primitive {
+                    Primitive::Circles => self.discs.len(),
+                    Primitive::Triangles => self.triangles.len(),
+                };
+                self.runs.push(Run {
+                    primitive,
+                    range: start..end,
+                });
+            }
+        }
+    }
+    pub fn circle(
+        &mut self,
+        position: Vec2<f32>,
+        inner_radius: f32,
+        outer_radius: f32,
+        color: Color<f32>,
+    ) {
+        self.extend_run(Primitive::Circles, self.discs.len() + 1);
+        self.discs.push(Disc {
+            pos: position,
+            inner_radius,
+            outer_radius,
+            color,
+        });
+    }
+    pub fn fade(&mut self, alpha: f32) {
+        for disc in &mut self.discs {
+            disc.color.a *= alpha;
+        }
+        for vertex in &mut self.triangles {
+            vertex.color.a *= alpha;
+        }
+    }
+    pub fn append(&mut self, other: Batch) {
+        for run in &other.runs {
+            let range = run.range.clone();
+            match run.primitive {
+                Primitive::Circles => {
+                    self.extend_run(Primitive::Circles, self.discs.len() + range.len());
+                    self.discs.extend_from_slice(&other.discs[range]);
+                }
+                Primitive::Triangles => {
+                    self.extend_run(Primitive::Triangles, self.triangles.len() + range.len());
+                    self.triangle_edges
+                        .extend_from_slice(&other.triangle_edges[range.start / 3..range.end / 3]);
+                    self.triangles.extend_from_slice(&other.triangles[range]);
+                }
+            }
+        }
+    }
+    fn triangle(&mut self, vertices: [Vec2<f32>; 3], edges: [bool; 3], color: Color<f32>) {
+        self.extend_run(Primitive::Triangles, self.triangles.len() + 3);
+        self.triangles
+            .extend(vertices.iter().map(|&pos| TriangleVertex { pos, color }));
+        self.triangle_edges.push(edges);
+    }
+    fn fan(&mut self, vertices: &[Vec2<f32>], outer: &[bool], color: Color<f32>) {
+        let n = vertices.len();
+        for i in 2..n {
+            self.triangle(
+                [vertices[0], vertices[i - 1], vertices[i]],
+                [i == 2 && outer[0], outer[i - 1], i == n - 1 && outer[n - 1]],
+                color,
+            );
+        }
+    }
+    pub fn polygon(&mut self, vertices: &[Vec2<f32>], color: Color<f32>) {
+        self.fan(vertices, &vec![true; vertices.len()], color);
+    }
+    pub fn line(&mut self, from: Vec2<f32>, to: Vec2<f32>, width: f32, color: Color<f32>) {
+        let len = (to - from).len();
+        if len < 1e-6 {
+            return;
+        }
+        let normal = vec2(from.y - to.y, to.x - from.x) * (width / 2.0 / len);
+        self.polygon(
+            &[from - normal, to - normal, to + normal, from + normal],
+            color,
+        );
+    }
+    pub fn arc(
+        &mut self,
+        center: Vec2<f32>,
+        radius: f32,
+        width: f32,
+        angle: std::ops::Range<f32>,
+        color: Color<f32>,
+    ) {
+        let n = ((angle.end - angle.start).abs() * radius.max(1.0) * 2.0)
+            .ceil()
+            .max(1.0) as usize;
+        let inner = radius - width / 2.0;
+        let outer = radius + width / 2.0;
+        let point = |i: usize, r: f32| {
+            let angle = angle.start + (angle.end - angle.start) * i as f32 / n as f32;
+            center + Vec2::rotated(vec2(r, 0.0), angle)
+        };
+        for i in 0..n {
+            self.fan(
+                &[
+                    point(i, inner),
+                    point(i, outer),
+                    point(i + 1, outer),
+                    point(i + 1, inner),
+                ],
+                &[i == 0, true, i == n - 1, true],
+                color,
+            );
+        }
+    }
+}
+
 pub struct Renderer {
-    quad: ugli::VertexBuffer<Vertex>,
+    vertices: RefCell<ugli::VertexBuffer<BatchVertex>>,
     program: ugli::Program,
+    edge_softness: Cell<f32>,
 }
 
 impl Renderer {
     pub fn new(geng: &Rc<Geng>) -> Self {
         Self {
-            quad: ugli::VertexBuffer::new_static(
-                geng.ugli(),
-                vec![
-                    Vertex {
-                        a_pos: vec2(0.0, 0.0),
-                    },
-                    Vertex {
-                        a_pos: vec2(1.0, 0.0),
-                    },
-                    Vertex {
-                        a_pos: vec2(1.0, 1.0),
-                    },
-                    Vertex {
-                        a_pos: vec2(0.0, 1.0),
-                    },
-                ],
-            ),
+            vertices: RefCell::new(ugli::VertexBuffer::new_dynamic(geng.ugli(), Vec::new())),
             program: geng
                 .shader_lib()
                 .compile(include_str!("program.glsl"))
                 .unwrap(),
+            edge_softness: Cell::new(0.0),
         }
     }
-    pub fn draw(
+    pub fn set_edge_softness(&self, edge_softness: f32) {
+        self.edge_softness.set(edge_softness);
+    }
+    pub fn draw_batch(&self, framebuffer: &mut ugli::Framebuffer, camera: &Camera, batch: &Batch) {
+        self.draw_batch_with(framebuffer, camera, batch, Some(default()));
+    }
+    pub fn draw_batch_with(
         &self,
         framebuffer: &mut ugli::Framebuffer,
         camera: &Camera,
-        position: Vec2<f32>,
-        inner_radius: f32,
-        outer_radius: f32,
-        color: Color<f32>,
+        batch: &Batch,
+        blend_mode: Option<ugli::BlendMode>,
     ) {
-        let camera_uniforms = camera.uniforms(framebuffer.size().map(|x| x as f32));
-        let uniforms = (
-            camera_uniforms,
-            ugli::uniforms! {
-                u_model_matrix: Mat4::translate(position.extend(0.0)) * Mat4::scale_uniform(outer_radius * 2.0) * Mat4::translate(vec3(-0.5, -0.5, 0.0)),
-                u_color: color,
-                u_inner: inner_radius / outer_radius,
-            },
-        );
+        if batch.runs.is_empty() {
+            return;
+        }
+        let mut vertices = self.vertices.borrow_mut();
+        vertices.clear();
+        for run in &batch.runs {
+            match run.primitive {
+                Primitive::Circles => {
+                    for disc in &batch.discs[run.range.clone()] {
+                        let outer = disc.outer_radius;
+                        for &corner in &[
+                            vec2(-1.0, -1.0),
+                            vec2(1.0, -1.0),
+                            vec2(1.0, 1.0),
+                            vec2(-1.0, -1.0),
+                            vec2(1.0, 1.0),
+                            vec2(-1.0, 1.0),
+                        ] {
+                            vertices.push(BatchVertex {
+                                a_pos: disc.pos + corner * outer,
+                                a_vt: corner,
+                                a_inner: disc.inner_radius / outer,
+                                a_radius: outer,
+                                a_edges: vec3(NO_EDGE, NO_EDGE, NO_EDGE),
+                                a_color: disc.color,
+                            });
+                        }
+                    }
+                }
+                Primitive::Triangles => {
+                    for start in run.range.clone().step_by(3) {
+                        let triangle = &batch.triangles[start..start + 3];
+                        let edges = batch.triangle_edges[start / 3];
+                        let distance = |k: usize, pos: Vec2<f32>| {
+                            if edges[k] {
+                                edge_distance(triangle[k].pos, triangle[(k + 1) % 3].pos, pos)
+                            } else {
+                                NO_EDGE
+                            }
+                        };
+                        for vertex in triangle {
+                            vertices.push(BatchVertex {
+                                a_pos: vertex.pos,
+                                a_vt: vec2(0.0, 0.0),
+                                a_inner: 0.0,
+                                a_radius: NO_EDGE,
+                                a_edges: vec3(
+                                    distance(0, vertex.pos),
+                                    distance(1, vertex.pos),
+                                    distance(2, vertex.pos),
+                                ),
+                                a_color: vertex.color,
+                            });
+                        }
+                    }
+                }
+            }
+        }
         ugli::draw(
             framebuffer,
             &self.program,
-            ugli::DrawMode::TriangleFan,
-            &self.quad,
-            uniforms,
+            ugli::DrawMode::Triangles,
+            &*vertices,
+            (
+                camera.uniforms(framebuffer.size().map(|x| x as f32)),
+                ugli::uniforms! {
+                    u_edge_softness: self.edge_softness.get(),
+                    u_pixel_size: camera.fov / framebuffer.size().y as f32,
+                },
+            ),
             ugli::DrawParameters {
-                blend_mode: Some(default()),
+                blend_mode,
                 ..default()
             },
         );
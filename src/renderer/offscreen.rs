@@ -0,0 +1,112 @@
+use super::*;
+
+const LETTERBOX_ASPECT: f32 = 16.0 / 9.0;
+
+#[derive(Clone, Copy)]
+pub struct Viewport {
+    pub pos: Vec2<f32>,
+    pub size: Vec2<f32>,
+}
+
+impl Viewport {
+    pub fn full(framebuffer_size: Vec2<f32>) -> Self {
+        Self {
+            pos: vec2(0.0, 0.0),
+            size: framebuffer_size,
+        }
+    }
+    pub fn world_to_screen(&self, camera: &Camera, pos: Vec2<f32>) -> Vec2<f32> {
+        self.pos + camera.world_to_screen(self.size, pos)
+    }
+}
+
+pub struct Offscreen {
+    texture: RefCell<Option<ugli::Texture>>,
+    post_process: PostProcess,
+}
+
+impl Offscreen {
+    pub fn new(geng: &Rc<Geng>) -> Self {
+        Self {
+            texture: RefCell::new(None),
+            post_process: PostProcess::new(geng),
+        }
+    }
+    fn layout(settings: &Settings, framebuffer_size: Vec2<usize>) -> (Vec2<usize>, Viewport) {
+        let height = settings.pixel_height.max(1);
+        let screen = framebuffer_size.map(|x| x as f32);
+        if !settings.pixelated {
+            return (framebuffer_size, Viewport::full(screen));
+        }
+        match settings.scaling {
+            Scaling::Stretch => (
+                vec2(height * framebuffer_size.x / framebuffer_size.y, height),
+                Viewport::full(screen),
+            ),
+            Scaling::Integer => {
+                let scale = (framebuffer_size.y / height).max(1);
+                let size = vec2(framebuffer_size.x / scale, height);
+                let viewport_size = size.map(|x| (x * scale) as f32);
+                (
+                    size,
+                    Viewport {
+                        pos: ((screen - viewport_size) / 2.0).map(|x| x.floor()),
+                        size: viewport_size,
+                    },
+                )
+            }
+            Scaling::Letterbox => {
+                let size = vec2((height as f32 * LETTERBOX_ASPECT) as usize, height);
+                let scale = (screen.x / size.x as f32).min(screen.y / size.y as f32);
+                let viewport_size = size.map(|x| x as f32 * scale);
+                (
+                    size,
+                    Viewport {
+                        pos: (screen - viewport_size) / 2.0,
+                        size: viewport_size,
+                    },
+                )
+            }
+        }
+    }
+    pub fn draw(
+        &self,
+        geng: &Geng,
+        framebuffer: &mut ugli::Framebuffer,
+        settings: &Settings,
+        velocity: Vec2<f32>,
+        draw: impl FnOnce(&mut ugli::Framebuffer),
+    ) -> Viewport {
+        if framebuffer.size().x == 0 || framebuffer.size().y == 0 {
+            return Viewport::full(framebuffer.size().map(|x| x as f32));
+        }
+        let (size, viewport) = Self::layout(settings, framebuffer.size());
+        let size = size.map(|x| x.max(1));
+        let mut texture = self.texture.borrow_mut();
+        if texture.as_ref().map(|texture| texture.size()) != Some(size) {
+            *texture = Some(ugli::Texture::new_uninitialized(geng.ugli(), size));
+        }
+        let texture = texture.as_mut().unwrap();
+        texture.set_filter(settings.pixel_filter.ugli());
+        draw(&mut ugli::Framebuffer::new_color(
+            geng.ugli(),
+            ugli::ColorAttachment::Texture(texture),
+        ));
+        ugli::clear(framebuffer, Some(Color::BLACK), None);
+        if !settings.post_passes().is_empty() {
+            self.post_process
+                .draw(geng, framebuffer, viewport, texture, settings, velocity);
+            return viewport;
+        }
+        geng.draw_2d().textured_quad(
+            framebuffer,
+            AABB::pos_size(
+                viewport.pos + vec2(0.0, viewport.size.y),
+                vec2(viewport.size.x, -viewport.size.y),
+            ),
+            texture,
+            Color::WHITE,
+        );
+        viewport
+    }
+}
@@ -0,0 +1,143 @@
+use super::*;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Pass {
+    SpeedBlur,
+    Palette,
+    Vignette,
+    Crt,
+}
+
+pub struct PostProcess {
+    quad: ugli::VertexBuffer<Vertex>,
+    palette: ugli::Program,
+    speed_blur: ugli::Program,
+    vignette: ugli::Program,
+    crt: ugli::Program,
+    textures: RefCell<Vec<ugli::Texture>>,
+}
+
+fn compile(geng: &Geng, fragment: &str) -> ugli::Program {
+    geng.shader_lib()
+        .compile(&format!(
+            "{}\n{}",
+            include_str!("postprocess/quad.glsl"),
+            fragment
+        ))
+        .unwrap()
+}
+
+impl PostProcess {
+    pub fn new(geng: &Rc<Geng>) -> Self {
+        Self {
+            quad: ugli::VertexBuffer::new_static(
+                geng.ugli(),
+                vec![
+                    Vertex {
+                        a_pos: vec2(0.0, 0.0),
+                    },
+                    Vertex {
+                        a_pos: vec2(1.0, 0.0),
+                    },
+                    Vertex {
+                        a_pos: vec2(1.0, 1.0),
+                    },
+                    Vertex {
+                        a_pos: vec2(0.0, 1.0),
+                    },
+                ],
+            ),
+            palette: compile(geng, include_str!("postprocess/palette.glsl")),
+            speed_blur: compile(geng, include_str!("postprocess/speed_blur.glsl")),
+            vignette: compile(geng, include_str!("postprocess/vignette.glsl")),
+            crt: compile(geng, include_str!("postprocess/crt.glsl")),
+            textures: RefCell::new(Vec::new()),
+        }
+    }
+    fn draw_pass(
+        &self,
+        pass: Pass,
+        framebuffer: &mut ugli::Framebuffer,
+        viewport: Viewport,
+        source: &ugli::Texture,
+        velocity: Vec2<f32>,
+    ) {
+        let program = match pass {
+            Pass::Palette => &self.palette,
+            Pass::SpeedBlur => &self.speed_blur,
+            Pass::Vignette => &self.vignette,
+            Pass::Crt => &self.crt,
+        };
+        ugli::draw(
+            framebuffer,
+            program,
+            ugli::DrawMode::TriangleFan,
+            &self.quad,
+            (
+                ugli::uniforms! {
+                    u_viewport_pos: viewport.pos,
+                    u_viewport_size: viewport.size,
+                    u_framebuffer_size: framebuffer.size().map(|x| x as f32),
+                    u_texture: source,
+                    u_texture_size: source.size().map(|x| x as f32),
+                    u_target_size: viewport.size,
+                },
+                ugli::uniforms! {
+                    u_levels: 4.0,
+                    u_velocity: vec2(velocity.x * source.size().y as f32 / source.size().x as f32, velocity.y),
+                    u_strength: 0.6,
+                    u_curvature: 0.1,
+                },
+            ),
+            ugli::DrawParameters { ..default() },
+        );
+    }
+    pub fn draw(
+        &self,
+        geng: &Geng,
+        framebuffer: &mut ugli::Framebuffer,
+        viewport: Viewport,
+        source: &ugli::Texture,
+        settings: &Settings,
+        velocity: Vec2<f32>,
+    ) {
+        let passes = settings.post_passes();
+        let (last, passes) = match passes.split_last() {
+            Some(split) => split,
+            None => return,
+        };
+        let size = source.size();
+        let mut textures = self.textures.borrow_mut();
+        if textures.first().map(|texture| texture.size()) != Some(size) {
+            *textures = (0..2)
+                .map(|_| ugli::Texture::new_uninitialized(geng.ugli(), size))
+                .collect();
+        }
+        let (a, b) = textures.split_at_mut(1);
+        let (a, b) = (&mut a[0], &mut b[0]);
+        a.set_filter(settings.pixel_filter.ugli());
+        b.set_filter(settings.pixel_filter.ugli());
+        for (index, &pass) in passes.iter().enumerate() {
+            let (source, target) = match index {
+                0 => (source, &mut *a),
+                _ if index % 2 == 0 => (&*b, &mut *a),
+                _ => (&*a, &mut *b),
+            };
+            let mut framebuffer =
+                ugli::Framebuffer::new_color(geng.ugli(), ugli::ColorAttachment::Texture(target));
+            self.draw_pass(
+                pass,
+                &mut framebuffer,
+                Viewport::full(size.map(|x| x as f32)),
+                source,
+                velocity,
+            );
+        }
+        let source = match passes.len() {
+            0 => source,
+            n if n % 2 == 1 => &*a,
+            _ => &*b,
+        };
+        self.draw_pass(*last, framebuffer, viewport, source, velocity);
+    }
+}
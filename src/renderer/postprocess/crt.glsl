@@ -0,0 +1,28 @@
+#ifdef FRAGMENT_SHADER
+uniform sampler2D u_texture;
+uniform vec2 u_target_size;
+uniform float u_curvature;
+
+void main() {
+    vec2 uv = v_uv - vec2(0.5);
+    uv *= 1.0 + dot(uv, uv) * u_curvature;
+    uv += vec2(0.5);
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        gl_FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+    vec3 color = texture2D(u_texture, uv).rgb;
+    vec2 pixel = v_uv * u_target_size;
+    float scanline = 0.75 + 0.25 * sin(pixel.y * 3.14159 * 2.0 / 3.0);
+    float column = mod(floor(pixel.x), 3.0);
+    vec3 mask = vec3(0.85);
+    if (column < 1.0) {
+        mask.r = 1.0;
+    } else if (column < 2.0) {
+        mask.g = 1.0;
+    } else {
+        mask.b = 1.0;
+    }
+    gl_FragColor = vec4(color * scanline * mask, 1.0);
+}
+#endif
@@ -0,0 +1,21 @@
+#ifdef FRAGMENT_SHADER
+uniform sampler2D u_texture;
+uniform vec2 u_texture_size;
+uniform float u_levels;
+
+float bayer2(vec2 pos) {
+    pos = floor(pos);
+    return fract(pos.x / 2.0 + pos.y * pos.y * 0.75);
+}
+
+float bayer4(vec2 pos) {
+    return bayer2(pos * 0.5) * 0.25 + bayer2(pos);
+}
+
+void main() {
+    vec4 color = texture2D(u_texture, v_uv);
+    float threshold = bayer4(v_uv * u_texture_size);
+    vec3 quantized = floor(color.rgb * (u_levels - 1.0) + threshold) / (u_levels - 1.0);
+    gl_FragColor = vec4(quantized, 1.0);
+}
+#endif
@@ -0,0 +1,15 @@
+varying vec2 v_uv;
+
+#ifdef VERTEX_SHADER
+attribute vec2 a_pos;
+
+uniform vec2 u_viewport_pos;
+uniform vec2 u_viewport_size;
+uniform vec2 u_framebuffer_size;
+
+void main() {
+    v_uv = a_pos;
+    vec2 pos = (u_viewport_pos + a_pos * u_viewport_size) / u_framebuffer_size;
+    gl_Position = vec4(pos * 2.0 - 1.0, 0.0, 1.0);
+}
+#endif
@@ -0,0 +1,17 @@
+#ifdef FRAGMENT_SHADER
+uniform sampler2D u_texture;
+uniform vec2 u_velocity;
+
+const int SAMPLES = 8;
+
+void main() {
+    vec3 sum = vec3(0.0);
+    for (int i = 0; i < SAMPLES; i++) {
+        vec2 uv = v_uv - u_velocity * (float(i) / float(SAMPLES - 1));
+        sum.r += texture2D(u_texture, uv + u_velocity * 0.5).r;
+        sum.g += texture2D(u_texture, uv).g;
+        sum.b += texture2D(u_texture, uv - u_velocity * 0.5).b;
+    }
+    gl_FragColor = vec4(sum / float(SAMPLES), 1.0);
+}
+#endif
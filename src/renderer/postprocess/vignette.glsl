@@ -0,0 +1,10 @@
+#ifdef FRAGMENT_SHADER
+uniform sampler2D u_texture;
+uniform float u_strength;
+
+void main() {
+    vec4 color = texture2D(u_texture, v_uv);
+    float dist = length(v_uv - vec2(0.5)) * 1.414;
+    gl_FragColor = vec4(color.rgb * (1.0 - u_strength * smoothstep(0.4, 1.0, dist)), 1.0);
+}
+#endif
@@ -1,26 +1,58 @@
 varying vec2 v_vt;
+varying float v_inner;
+varying float v_radius;
+varying vec3 v_edges;
+varying vec4 v_color;
 
 #ifdef VERTEX_SHADER
 attribute vec2 a_pos;
+attribute vec2 a_vt;
+attribute float a_inner;
+attribute float a_radius;
+attribute vec3 a_edges;
+attribute vec4 a_color;
 
 uniform mat4 u_projection_matrix;
 uniform mat4 u_view_matrix;
-uniform mat4 u_model_matrix;
 
 void main() {
-    v_vt = vec2(a_pos.x, 1.0 - a_pos.y);
-    gl_Position = u_projection_matrix * u_view_matrix * u_model_matrix * vec4(a_pos, 0.0, 1.0);
+    v_vt = a_vt;
+    v_inner = a_inner;
+    v_radius = a_radius;
+    v_edges = a_edges;
+    v_color = a_color;
+    gl_Position = u_projection_matrix * u_view_matrix * vec4(a_pos, 0.0, 1.0);
 }
 #endif
 
 #ifdef FRAGMENT_SHADER
-uniform vec4 u_color;
-uniform float u_inner;
+uniform float u_edge_softness;
+uniform float u_pixel_size;
+
+// Triangles carry a NO_EDGE radius and only use their edge distances.
+const float NO_EDGE = 1000.0;
+
 void main() {
-    float len = length(v_vt - vec2(0.5, 0.5)) * 2.0;
-    if (len > 1.0 || len < u_inner) {
+    float width = u_pixel_size * u_edge_softness;
+    float coverage = 1.0;
+    if (v_radius < NO_EDGE) {
+        float len = length(v_vt);
+        if (u_edge_softness > 0.0) {
+            coverage = clamp((1.0 - len) * v_radius / width, 0.0, 1.0);
+            if (v_inner > 0.0) {
+                coverage = min(coverage, clamp((len - v_inner) * v_radius / width, 0.0, 1.0));
+            }
+        } else {
+            coverage = len > 1.0 || len < v_inner ? 0.0 : 1.0;
+        }
+    }
+    if (u_edge_softness > 0.0) {
+        float edge = min(min(v_edges.x, v_edges.y), v_edges.z);
+        coverage = min(coverage, clamp(edge / width, 0.0, 1.0));
+    }
+    if (coverage <= 0.0) {
         discard;
     }
-    gl_FragColor = u_color;
+    gl_FragColor = vec4(v_color.rgb, v_color.a * coverage);
 }
-#endif
\ No newline at end of file
+#endif
@@ -0,0 +1,270 @@
+use super::*;
+
+fn blend(dst: Color<f32>, src: Color<f32>) -> Color<f32> {
+    let a = src.a;
+    Color::rgba(
+        src.r * a + dst.r * (1.0 - a),
+        src.g * a + dst.g * (1.0 - a),
+        src.b * a + dst.b * (1.0 - a),
+        src.a * a + dst.a * (1.0 - a),
+    )
+}
+
+fn to_u8(x: f32) -> u8 {
+    (x.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+struct Target {
+    size: Vec2<usize>,
+    framebuffer_size: Vec2<f32>,
+    matrix: Mat4<f32>,
+    inverse: Mat4<f32>,
+    pixels: Vec<Color<f32>>,
+}
+
+impl Target {
+    fn to_pixels(&self, pos: Vec2<f32>) -> Vec2<f32> {
+        let pos = self.matrix * pos.extend(0.0).extend(1.0);
+        vec2(
+            (pos.x + 1.0) / 2.0 * self.framebuffer_size.x,
+            (pos.y + 1.0) / 2.0 * self.framebuffer_size.y,
+        )
+    }
+    fn to_world(&self, x: usize, y: usize) -> Vec2<f32> {
+        let ndc = vec2(
+            (x as f32 + 0.5) / self.framebuffer_size.x * 2.0 - 1.0,
+            (y as f32 + 0.5) / self.framebuffer_size.y * 2.0 - 1.0,
+        );
+        (self.inverse * ndc.extend(0.0).extend(1.0)).xy()
+    }
+    fn pixel_ranges(
+        &self,
+        points: impl IntoIterator<Item = Vec2<f32>>,
+    ) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        let mut min = vec2(f32::INFINITY, f32::INFINITY);
+        let mut max = vec2(-f32::INFINITY, -f32::INFINITY);
+        for pos in points {
+            let pos = self.to_pixels(pos);
+            min = vec2(min.x.min(pos.x), min.y.min(pos.y));
+            max = vec2(max.x.max(pos.x), max.y.max(pos.y));
+        }
+        (
+            (min.x.max(0.0) as usize)..(max.x.max(0.0).ceil() as usize).min(self.size.x),
+            (min.y.max(0.0) as usize)..(max.y.max(0.0).ceil() as usize).min(self.size.y),
+        )
+    }
+    fn blend(&mut self, x: usize, y: usize, color: Color<f32>) {
+        let pixel = &mut self.pixels[y * self.size.x + x];
+        *pixel = blend(*pixel, color);
+    }
+    fn circle(&mut self, disc: &Disc, edge_softness: f32) {
+        let pixel = (self.inverse * vec4(0.0, 2.0 / self.framebuffer_size.y, 0.0, 0.0))
+            .xy()
+            .len();
+        let outer = disc.outer_radius;
+        let inner = disc.inner_radius / outer;
+        let (x_range, y_range) = self.pixel_ranges(
+            [
+                vec2(-1.0, -1.0),
+                vec2(1.0, -1.0),
+                vec2(1.0, 1.0),
+                vec2(-1.0, 1.0),
+            ]
+            .iter()
+            .map(|&corner| disc.pos + corner * outer),
+        );
+        for y in y_range {
+            for x in x_range.clone() {
+                let delta_pos = self.to_world(x, y) - disc.pos;
+                let len = delta_pos.len() / outer;
+                let mut coverage = if len > 1.0 || len < inner { 0.0 } else { 1.0 };
+                if edge_softness > 0.0 {
+                    let width = pixel / outer * edge_softness;
+                    coverage = ((1.0 - len) / width).clamp(0.0, 1.0);
+                    if inner > 0.0 {
+                        coverage = coverage.min(((len - inner) / width).clamp(0.0, 1.0));
+                    }
+                }
+                if coverage <= 0.0 {
+                    continue;
+                }
+                let mut color = disc.color;
+                color.a *= coverage;
+                self.blend(x, y, color);
+            }
+        }
+    }
+    fn triangle(&mut self, vertices: &[TriangleVertex], outer: [bool; 3], edge_softness: f32) {
+        let pixel = (self.inverse * vec4(0.0, 2.0 / self.framebuffer_size.y, 0.0, 0.0))
+            .xy()
+            .len();
+        let mut points = [vertices[0].pos, vertices[1].pos, vertices[2].pos];
+        if Vec2::skew(points[1] - points[0], points[2] - points[0]) < 0.0 {
+            points.swap(1, 2);
+        }
+        let edges = [
+            (points[0], points[1]),
+            (points[1], points[2]),
+            (points[2], points[0]),
+        ];
+        let (x_range, y_range) = self.pixel_ranges(points.iter().copied());
+        for y in y_range {
+            for x in x_range.clone() {
+                let pos = self.to_world(x, y);
+                let inside = edges.iter().all(|&(a, b)| {
+                    let side = Vec2::skew(b - a, pos - a);
+                    side > 0.0 || (side == 0.0 && (b.y < a.y || (b.y == a.y && b.x > a.x)))
+                });
+                if !inside {
+                    continue;
+                }
+                let mut color = vertices[0].color;
+                if edge_softness > 0.0 {
+                    let width = pixel * edge_softness;
+                    let edge = (0..3)
+                        .filter(|&k| outer[k])
+                        .map(|k| edge_distance(vertices[k].pos, vertices[(k + 1) % 3].pos, pos))
+                        .fold(NO_EDGE, f32::min);
+                    color.a *= (edge / width).clamp(0.0, 1.0);
+                }
+                if color.a > 0.0 {
+                    self.blend(x, y, color);
+                }
+            }
+        }
+    }
+}
+
+pub fn render(
+    size: Vec2<usize>,
+    camera: &Camera,
+    clear_color: Color<f32>,
+    edge_softness: f32,
+    batch: &Batch,
+) -> image::RgbaImage {
+    let framebuffer_size = size.map(|x| x as f32);
+    let matrix = camera.view_projection_matrix(framebuffer_size);
+    let mut target = Target {
+        size,
+        framebuffer_size,
+        matrix,
+        inverse: matrix.inverse(),
+        pixels: vec![clear_color; size.x * size.y],
+    };
+    for run in &batch.runs {
+        match run.primitive {
+            Primitive::Circles => {
+                for disc in &batch.discs[run.range.clone()] {
+                    target.circle(disc, edge_softness);
+                }
+            }
+            Primitive::Triangles => {
+                for start in run.range.clone().step_by(3) {
+                    target.triangle(
+                        &batch.triangles[start..start + 3],
+                        batch.triangle_edges[start / 3],
+                        edge_softness,
+                    );
+                }
+            }
+        }
+    }
+    image::RgbaImage::from_fn(size.x as u32, size.y as u32, |x, y| {
+        let color = target.pixels[(size.y - 1 - y as usize) * size.x + x as usize];
+        image::Rgba([
+            to_u8(color.r),
+            to_u8(color.g),
+            to_u8(color.b),
+            to_u8(color.a),
+        ])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn themes() -> Themes {
+        Themes::parse(include_str!("../../static/themes.json"))
+    }
+
+    fn check_golden(name: &str, image: &image::RgbaImage) {
+        let path = format!("{}/tests/golden/{}.png", env!("CARGO_MANIFEST_DIR"), name);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            image.save(&path).unwrap();
+            return;
+        }
+        let golden = image::open(&path)
+            .unwrap_or_else(|e| panic!("Failed to open {}: {}", path, e))
+            .to_rgba8();
+        assert_eq!(golden.dimensions(), image.dimensions());
+        let matches = golden.pixels().zip(image.pixels()).all(|(a, b)| {
+            a.0.iter()
+                .zip(b.0.iter())
+                .all(|(&a, &b)| (a as i32 - b as i32).abs() <= 1)
+        });
+        if !matches {
+            image.save(path.replace(".png", ".actual.png")).unwrap();
+            panic!("{} does not match the golden image", name);
+        }
+    }
+
+    #[test]
+    fn track() {
+        let track = Track::parse(include_str!("../../static/track.json"));
+        let themes = themes();
+        let mut batch = Batch::new();
+        draw_track(
+            &mut batch,
+            &track,
+            themes.get(None),
+            &vec![false; track.orbs.len()],
+            0.0,
+        );
+        let camera = Camera::new(160.0);
+        check_golden(
+            "track",
+            &render(vec2(256, 256), &camera, Color::WHITE, 0.0, &batch),
+        );
+    }
+
+    #[test]
+    fn ship() {
+        let player = Player::new(vec2(0.0, 0.0), f32::PI / 2.0);
+        let themes = themes();
+        let mut batch = Batch::new();
+        draw_player(
+            &mut batch,
+            &player,
+            &ParticleSystem::new(0),
+            &ShipStyle::default(),
+            themes.get(None),
+            0.0,
+        );
+        let camera = Camera::new(5.0);
+        check_golden(
+            "ship",
+            &render(vec2(128, 128), &camera, Color::WHITE, 0.0, &batch),
+        );
+    }
+
+    #[test]
+    fn ship_smooth() {
+        let player = Player::new(vec2(0.0, 0.0), f32::PI / 2.0);
+        let themes = themes();
+        let mut batch = Batch::new();
+        draw_player(
+            &mut batch,
+            &player,
+            &ParticleSystem::new(0),
+            &ShipStyle::default(),
+            themes.get(None),
+            0.0,
+        );
+        let camera = Camera::new(5.0);
+        check_golden(
+            "ship_smooth",
+            &render(vec2(128, 128), &camera, Color::WHITE, 1.0, &batch),
+        );
+    }
+}
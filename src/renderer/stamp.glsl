@@ -0,0 +1,12 @@
+#ifdef FRAGMENT_SHADER
+uniform sampler2D u_texture;
+uniform sampler2D u_marks;
+
+void main() {
+    vec4 dst = texture2D(u_texture, v_uv);
+    vec4 src = texture2D(u_marks, v_uv);
+    float a = max(dst.a, src.a);
+    vec3 color = a > 0.0 ? mix(dst.rgb, src.rgb, src.a / a) : dst.rgb;
+    gl_FragColor = vec4(color, a);
+}
+#endif
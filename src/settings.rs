@@ -0,0 +1,124 @@
+use super::*;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Scaling {
+    Stretch,
+    Integer,
+    Letterbox,
+}
+
+impl Scaling {
+    pub const ALL: [Self; 3] = [Self::Stretch, Self::Integer, Self::Letterbox];
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Stretch => "STRETCH",
+            Self::Integer => "INTEGER",
+            Self::Letterbox => "LETTERBOX",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum PixelFilter {
+    Nearest,
+    Linear,
+}
+
+impl PixelFilter {
+    pub const ALL: [Self; 2] = [Self::Nearest, Self::Linear];
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Nearest => "NEAREST",
+            Self::Linear => "LINEAR",
+        }
+    }
+    pub fn ugli(self) -> ugli::Filter {
+        match self {
+            Self::Nearest => ugli::Filter::Nearest,
+            Self::Linear => ugli::Filter::Linear,
+        }
+    }
+}
+
+pub const CAMERA_STIFFNESSES: [f32; 5] = [3.0, 4.5, 6.0, 9.0, 14.0];
+
+pub const PIXEL_HEIGHTS: [usize; 5] = [100, 150, 200, 300, 400];
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub smooth_edges: bool,
+    pub edge_softness: f32,
+    pub pixelated: bool,
+    pub pixel_height: usize,
+    pub pixel_filter: PixelFilter,
+    pub scaling: Scaling,
+    pub palette: bool,
+    pub speed_blur: bool,
+    pub vignette: bool,
+    pub crt: bool,
+    pub theme: Option<String>,
+    pub ship: ShipStyle,
+    pub camera_mode: CameraMode,
+    pub screen_shake: bool,
+    pub camera_stiffness: f32,
+    pub minimap: bool,
+    pub racing_line: bool,
+    pub mixer: Mixer,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            smooth_edges: false,
+            edge_softness: 1.0,
+            pixelated: true,
+            pixel_height: 200,
+            pixel_filter: PixelFilter::Nearest,
+            scaling: Scaling::Stretch,
+            palette: false,
+            speed_blur: false,
+            vignette: false,
+            crt: false,
+            theme: None,
+            ship: ShipStyle::default(),
+            camera_mode: CameraMode::Follow,
+            screen_shake: true,
+            camera_stiffness: DEFAULT_STIFFNESS,
+            minimap: true,
+            racing_line: false,
+            mixer: Mixer::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn edge_softness(&self) -> f32 {
+        if self.smooth_edges {
+            self.edge_softness
+        } else {
+            0.0
+        }
+    }
+    pub fn post_passes(&self) -> Vec<Pass> {
+        let mut passes = Vec::new();
+        if self.speed_blur {
+            passes.push(Pass::SpeedBlur);
+        }
+        if self.palette {
+            passes.push(Pass::Palette);
+        }
+        if self.vignette {
+            passes.push(Pass::Vignette);
+        }
+        if self.crt {
+            passes.push(Pass::Crt);
+        }
+        passes
+    }
+}
+
+pub fn cycle<T: Copy + PartialEq>(values: &[T], value: T, delta: i32) -> T {
+    let index = values.iter().position(|&x| x == value).unwrap_or(0) as i32;
+    values[(index + delta).rem_euclid(values.len() as i32) as usize]
+}
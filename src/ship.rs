@@ -0,0 +1,169 @@
+use super::*;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ShipColor {
+    Theme,
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Cyan,
+    Blue,
+    Purple,
+    Pink,
+    White,
+    Black,
+}
+
+impl ShipColor {
+    pub const ALL: [Self; 11] = [
+        Self::Theme,
+        Self::Red,
+        Self::Orange,
+        Self::Yellow,
+        Self::Green,
+        Self::Cyan,
+        Self::Blue,
+        Self::Purple,
+        Self::Pink,
+        Self::White,
+        Self::Black,
+    ];
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Theme => "THEME",
+            Self::Red => "RED",
+            Self::Orange => "ORANGE",
+            Self::Yellow => "YELLOW",
+            Self::Green => "GREEN",
+            Self::Cyan => "CYAN",
+            Self::Blue => "BLUE",
+            Self::Purple => "PURPLE",
+            Self::Pink => "PINK",
+            Self::White => "WHITE",
+            Self::Black => "BLACK",
+        }
+    }
+    pub fn color(self, theme_color: Color<f32>) -> Color<f32> {
+        match self {
+            Self::Theme => theme_color,
+            Self::Red => Color::rgb(0.9, 0.1, 0.1),
+            Self::Orange => Color::rgb(1.0, 0.5, 0.0),
+            Self::Yellow => Color::rgb(1.0, 0.85, 0.1),
+            Self::Green => Color::rgb(0.1, 0.7, 0.2),
+            Self::Cyan => Color::rgb(0.1, 0.8, 0.9),
+            Self::Blue => Color::rgb(0.1, 0.3, 1.0),
+            Self::Purple => Color::rgb(0.6, 0.2, 0.9),
+            Self::Pink => Color::rgb(1.0, 0.4, 0.7),
+            Self::White => Color::rgb(0.95, 0.95, 0.95),
+            Self::Black => Color::rgb(0.1, 0.1, 0.1),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum TrailStyle {
+    Classic,
+    Fade,
+    Fire,
+    Smoke,
+    Rainbow,
+}
+
+fn hue(h: f32) -> Color<f32> {
+    let channel = |offset: f32| {
+        let x = ((h + offset).fract() * 6.0 - 3.0).abs();
+        (x - 1.0).clamp(0.0, 1.0)
+    };
+    Color::rgb(channel(0.0), channel(2.0 / 3.0), channel(1.0 / 3.0))
+}
+
+impl TrailStyle {
+    pub const ALL: [Self; 5] = [
+        Self::Classic,
+        Self::Fade,
+        Self::Fire,
+        Self::Smoke,
+        Self::Rainbow,
+    ];
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Classic => "CLASSIC",
+            Self::Fade => "FADE",
+            Self::Fire => "FIRE",
+            Self::Smoke => "SMOKE",
+            Self::Rainbow => "RAINBOW",
+        }
+    }
+    pub fn exhaust(self, color: Color<f32>) -> Emitter {
+        let transparent = |color: Color<f32>| Color { a: 0.0, ..color };
+        match self {
+            Self::Classic => Emitter::exhaust(vec![color], [0.2, 0.2]),
+            Self::Fade => Emitter::exhaust(vec![color, transparent(color)], [0.2, 0.2]),
+            Self::Fire => Emitter::exhaust(
+                vec![
+                    Color::rgba(1.0, 0.9, 0.2, 0.8),
+                    Color::rgba(0.8, 0.1, 0.0, 0.0),
+                ],
+                [0.2, 0.06],
+            ),
+            Self::Smoke => Emitter::exhaust(
+                vec![
+                    Color::rgba(0.5, 0.5, 0.5, 0.4),
+                    Color::rgba(0.5, 0.5, 0.5, 0.0),
+                ],
+                [0.2, 0.6],
+            ),
+            Self::Rainbow => Emitter::exhaust(
+                (0..=6)
+                    .map(|i| {
+                        let t = i as f32 / 6.0;
+                        Color {
+                            a: 0.6 * (1.0 - t),
+                            ..hue(t)
+                        }
+                    })
+                    .collect(),
+                [0.2, 0.2],
+            ),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ExhaustCache(Option<(TrailStyle, Color<f32>, Rc<Emitter>)>);
+
+impl ExhaustCache {
+    pub fn get(&mut self, trail: TrailStyle, color: Color<f32>) -> Rc<Emitter> {
+        match &self.0 {
+            Some((cached_trail, cached_color, exhaust))
+                if *cached_trail == trail && *cached_color == color =>
+            {
+                exhaust.clone()
+            }
+            _ => {
+                let exhaust = Rc::new(trail.exhaust(color));
+                self.0 = Some((trail, color, exhaust.clone()));
+                exhaust
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ShipStyle {
+    pub head: ShipColor,
+    pub thrusters: ShipColor,
+    pub trail: TrailStyle,
+}
+
+impl Default for ShipStyle {
+    fn default() -> Self {
+        Self {
+            head: ShipColor::Theme,
+            thrusters: ShipColor::Theme,
+            trail: TrailStyle::Classic,
+        }
+    }
+}
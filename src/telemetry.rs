@@ -0,0 +1,66 @@
+use super::*;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Sample {
+    pub time: f32,
+    pub lap: i32,
+    pub penalty: f32,
+    pub valid: bool,
+    pub pos: Vec2<f32>,
+    pub vel: Vec2<f32>,
+    pub rotation: f32,
+    pub w: f32,
+    pub left_thruster: bool,
+    pub right_thruster: bool,
+    pub collisions: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Telemetry {
+    #[serde(default)]
+    pub track: u64,
+    pub samples: Vec<Sample>,
+}
+
+impl Telemetry {
+    pub fn new(track: &Track) -> Self {
+        Self {
+            track: track.id(),
+            samples: Vec::new(),
+        }
+    }
+    pub fn record(&mut self, sample: Sample) {
+        self.samples.push(sample);
+    }
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+    pub fn to_csv(&self) -> String {
+        let mut csv =
+            "time,lap,penalty,valid,x,y,vx,vy,rotation,w,left_thruster,right_thruster,collisions\n"
+                .to_owned();
+        for sample in &self.samples {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                sample.time,
+                sample.lap,
+                sample.penalty,
+                sample.valid as i32,
+                sample.pos.x,
+                sample.pos.y,
+                sample.vel.x,
+                sample.vel.y,
+                sample.rotation,
+                sample.w,
+                sample.left_thruster as i32,
+                sample.right_thruster as i32,
+                sample.collisions,
+            ));
+        }
+        csv
+    }
+    pub fn export(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path.with_extension("csv"), self.to_csv())?;
+        std::fs::write(path.with_extension("json"), self.to_json())
+    }
+}
@@ -0,0 +1,45 @@
+use super::*;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Theme {
+    pub name: String,
+    pub background: Color<f32>,
+    pub blobs: Color<f32>,
+    pub tire: Color<f32>,
+    pub skid: Color<f32>,
+    pub outline: Color<f32>,
+    pub start_line: [Color<f32>; 2],
+    pub oil: Color<f32>,
+    pub gravity: Color<f32>,
+    pub spin: Color<f32>,
+    pub boost_pad: Color<f32>,
+    pub boost_pad_fill: Color<f32>,
+    pub orb: Color<f32>,
+    pub head: Color<f32>,
+    pub thruster: Color<f32>,
+    pub thruster_tube: Color<f32>,
+    pub beam: Color<f32>,
+    pub exhaust: Color<f32>,
+    pub text: Color<f32>,
+    pub hint: Color<f32>,
+    pub panel: Color<f32>,
+}
+
+pub struct Themes {
+    themes: Vec<Theme>,
+}
+
+impl Themes {
+    pub fn parse(json: &str) -> Self {
+        let themes: Vec<Theme> = serde_json::from_str(json).expect("Failed to parse themes");
+        assert!(!themes.is_empty(), "No themes defined");
+        Self { themes }
+    }
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.themes.iter().map(|theme| theme.name.as_str())
+    }
+    pub fn get(&self, name: Option<&str>) -> &Theme {
+        name.and_then(|name| self.themes.iter().find(|theme| theme.name == name))
+            .unwrap_or(&self.themes[0])
+    }
+}
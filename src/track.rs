@@ -0,0 +1,169 @@
+use super::*;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Penalty {
+    Time(f32),
+    InvalidateLap,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Rules {
+    pub tire_hit: Option<Penalty>,
+    pub cut: Option<Penalty>,
+    pub min_hit_impulse: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct BoostPad {
+    pub pos: Vec2<f32>,
+    pub r: f32,
+    pub direction: f32,
+    pub strength: f32,
+}
+
+impl BoostPad {
+    pub fn circle(&self) -> Circle {
+        Circle {
+            pos: self.pos,
+            r: self.r,
+        }
+    }
+    pub fn impulse(&self) -> Vec2<f32> {
+        Vec2::rotated(vec2(self.strength, 0.0), self.direction)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Orb {
+    pub pos: Vec2<f32>,
+    pub r: f32,
+    pub score: i32,
+}
+
+impl Orb {
+    pub fn circle(&self) -> Circle {
+        Circle {
+            pos: self.pos,
+            r: self.r,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub enum HazardKind {
+    Oil { damping: f32 },
+    Gravity { strength: f32 },
+    Spin { w: f32 },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Hazard {
+    pub pos: Vec2<f32>,
+    pub r: f32,
+    pub kind: HazardKind,
+}
+
+impl Hazard {
+    pub fn apply(&self, pos: Vec2<f32>, field: &mut ForceField) {
+        let delta_pos = self.pos - pos;
+        let dist = delta_pos.len();
+        if dist >= self.r {
+            return;
+        }
+        match self.kind {
+            HazardKind::Oil { damping } => field.damping *= damping,
+            HazardKind::Gravity { strength } => {
+                if dist > 1e-3 {
+                    field.force += delta_pos / dist * strength * (1.0 - dist / self.r);
+                }
+            }
+            HazardKind::Spin { w } => field.torque += w,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Track {
+    pub spawn: Vec2<f32>,
+    pub spawn_rotation: f32,
+    pub start_line: [Vec2<f32>; 2],
+    pub tires: Vec<Circle>,
+    #[serde(default)]
+    pub boost_pads: Vec<BoostPad>,
+    #[serde(default)]
+    pub orbs: Vec<Orb>,
+    #[serde(default)]
+    pub hazards: Vec<Hazard>,
+    #[serde(default)]
+    pub infield: Vec<Circle>,
+    #[serde(default)]
+    pub rules: Option<Rules>,
+    #[serde(default)]
+    pub theme: Option<String>,
+    #[serde(default)]
+    pub cameras: Vec<Vec2<f32>>,
+    #[serde(default)]
+    pub centerline: Vec<Vec2<f32>>,
+}
+
+impl Track {
+    pub fn parse(json: &str) -> Self {
+        serde_json::from_str(json).expect("Failed to parse track")
+    }
+    pub fn id(&self) -> u64 {
+        serde_json::to_string(self)
+            .unwrap()
+            .bytes()
+            .fold(0xcbf29ce484222325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            })
+    }
+    pub fn crosses_start_line(&self, from: Vec2<f32>, to: Vec2<f32>) -> Option<bool> {
+        let [a, b] = self.start_line;
+        let side = |pos: Vec2<f32>| Vec2::skew(b - a, pos - a);
+        let from_side = side(from);
+        let to_side = side(to);
+        if (from_side < 0.0) == (to_side < 0.0) {
+            return None;
+        }
+        let pos = from + (to - from) * (from_side / (from_side - to_side));
+        let t = Vec2::dot(pos - a, b - a) / Vec2::dot(b - a, b - a);
+        if !(0.0..=1.0).contains(&t) {
+            return None;
+        }
+        Some(to_side >= 0.0)
+    }
+    pub fn bounds(&self) -> (Vec2<f32>, Vec2<f32>) {
+        const MARGIN: f32 = 10.0;
+        let mut min = self.spawn;
+        let mut max = self.spawn;
+        for pos in self
+            .tires
+            .iter()
+            .map(|tire| tire.pos)
+            .chain(self.start_line.iter().copied())
+        {
+            min = vec2(min.x.min(pos.x), min.y.min(pos.y));
+            max = vec2(max.x.max(pos.x), max.y.max(pos.y));
+        }
+        (min - vec2(MARGIN, MARGIN), max + vec2(MARGIN, MARGIN))
+    }
+    pub fn camera_spots(&self) -> Vec<Vec2<f32>> {
+        if !self.cameras.is_empty() {
+            return self.cameras.clone();
+        }
+        self.tires.iter().step_by(30).map(|tire| tire.pos).collect()
+    }
+    pub fn force_field(&self, pos: Vec2<f32>) -> ForceField {
+        let mut field = ForceField::default();
+        for hazard in &self.hazards {
+            hazard.apply(pos, &mut field);
+        }
+        field
+    }
+    pub fn is_cutting(&self, pos: Vec2<f32>) -> bool {
+        self.infield
+            .iter()
+            .any(|zone| (pos - zone.pos).len() < zone.r)
+    }
+}